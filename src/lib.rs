@@ -17,16 +17,192 @@ pub const DEFAULT_DST_QUAD: RectCorners = [
     (0., DST_SIZE),
 ];
 
+/** The canonical unit square, independent of `DST_SIZE` */
+const UNIT_SQUARE: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+
+/** Which way the y axis points when generating the default destination quad */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoordinateSystem {
+    /** Origin at the top left, y increasing downwards (as per `DEFAULT_DST_QUAD`) */
+    #[default]
+    ScreenYDown,
+    /** Origin at the bottom left, y increasing upwards */
+    MathYUp,
+}
+
+impl CoordinateSystem {
+    /** The 1x1 destination quad in this coordinate system, with corners in the usual
+    'left top', 'right top', 'right bottom', 'left bottom' order */
+    pub fn default_dst_quad(self) -> RectCorners {
+        match self {
+            CoordinateSystem::ScreenYDown => DEFAULT_DST_QUAD,
+            CoordinateSystem::MathYUp => [
+                (0., DST_SIZE),
+                (DST_SIZE, DST_SIZE),
+                (DST_SIZE, 0.),
+                (0., 0.),
+            ],
+        }
+    }
+}
+
+/** How `transform` treats points whose homogeneous w coordinate is not positive,
+i.e. points on or beyond the "horizon" of a perspective transform */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransformMode {
+    /** Always divide by w (if non-zero), so points behind the projection plane wrap
+    around to the other side */
+    #[default]
+    Standard,
+    /** Return an error for points behind the projection plane (negative w), and a
+    different error for points which map to (near) infinity (w close to zero) */
+    FrontOnly,
+}
+
+/** What `transform` (and the batch methods built on it, such as `transform_many`)
+does when called before the transformer is ready, i.e. before any source quad has
+been set */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotReadyPolicy {
+    /** Return an error */
+    #[default]
+    Error,
+    /** Return the input point unchanged */
+    Passthrough,
+}
+
+/** How the transform copes with source and destination quads of different aspect
+ratios (measured from their average edge lengths) */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AspectMode {
+    /** Map the source quad onto the destination quad exactly, stretching as needed */
+    #[default]
+    Stretch,
+    /** Preserve the source aspect ratio by cropping it: a centred region of the
+    source quad, inset on two sides, is mapped onto the whole destination quad, so
+    the output fills it and the rest of the source maps outside it */
+    Fill,
+}
+
+/** How batch methods (`transform_many` and those built on it) treat input points
+with a NaN or infinite coordinate, e.g. from a glitching sensor */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NanPolicy {
+    /** Leave such points out of the results, logging how many were skipped */
+    #[default]
+    Skip,
+    /** Return an error naming the first such point */
+    Fail,
+    /** Transform them like any other point, so the results contain NaN too */
+    PassThrough,
+}
+
+/** Summary of the reprojection errors over a set of correspondences; see
+`QuadTransformer::error_stats` */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorStats {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub median: f32,
+}
+
+/** The transform matrix in a plain C-compatible layout, for passing across an FFI
+boundary; see `QuadTransformer::to_ffi`. `matrix` is row-major, and is only
+meaningful if `ready` is true. */
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FfiTransform {
+    pub matrix: [f32; 9],
+    pub ready: bool,
+}
+
+/** Where a destination-space point lies relative to the destination quad; see
+`QuadTransformer::classify_point` */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointZone {
+    /** Inside the quad, and further than the edge band from its edges */
+    Inside,
+    /** Inside the quad, but within the edge band of its edges */
+    EdgeBand,
+    /** Outside the quad, but within the outside margin */
+    OutsideTolerated,
+    /** Outside the quad, beyond the outside margin */
+    Rejected,
+}
+
 /**
 clockwise: 'left top', 'right top', 'right bottom', 'left bottom',
  */
 pub type RectCorners = [Point2D; 4];
-type Matrix8x8 = na::SMatrix<f32, 8, 8>;
+pub type Matrix8x8 = na::SMatrix<f32, 8, 8>;
+pub type Matrix1x8 = na::SMatrix<f32, 1, 8>;
+type Vector8 = na::SVector<f32, 8>;
+
+/** How transformed coordinates are snapped to whole pixels */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelRounding {
+    /** Nearest integer, halfway cases away from zero */
+    Round,
+    Floor,
+    Ceil,
+    /** Towards zero */
+    Truncate,
+}
+
+impl PixelRounding {
+    pub fn apply(self, value: f32) -> i32 {
+        match self {
+            PixelRounding::Round => value.round() as i32,
+            PixelRounding::Floor => value.floor() as i32,
+            PixelRounding::Ceil => value.ceil() as i32,
+            PixelRounding::Truncate => value.trunc() as i32,
+        }
+    }
+}
+
+/** Header and version for the `to_bytes` / `from_bytes` format */
+const BYTES_MAGIC: &[u8; 3] = b"QTT";
+const BYTES_VERSION: u8 = 2;
+/** Magic, version, flags and options, followed by 9 matrix elements, 2 quads of 8
+values, the margin and the edge band, each a little-endian f32 */
+const BYTES_LEN: usize = 6 + (9 + 8 + 8 + 1 + 1) * 4;
+/** Version 1 had no options byte or edge band, so it is still read (with defaults
+for those) */
+const BYTES_LEN_V1: usize = 5 + (9 + 8 + 8 + 1) * 4;
 
+const FLAG_MATRIX: u8 = 1;
+const FLAG_SRC_QUAD: u8 = 1 << 1;
+const FLAG_DST_QUAD: u8 = 1 << 2;
+const FLAG_MARGIN: u8 = 1 << 3;
+const FLAG_FALLBACK_IDENTITY: u8 = 1 << 4;
+const FLAG_MATH_Y_UP: u8 = 1 << 5;
+const FLAG_FRONT_ONLY: u8 = 1 << 6;
+const FLAG_CENTERED_DESTINATION: u8 = 1 << 7;
+
+/** Bits of the second (options) byte, added in version 2 */
+const OPTION_ASPECT_FILL: u8 = 1;
+const OPTION_NOT_READY_PASSTHROUGH: u8 = 1 << 1;
+const OPTION_NAN_FAIL: u8 = 1 << 2;
+const OPTION_NAN_PASS_THROUGH: u8 = 1 << 3;
+
+#[derive(Clone)]
 pub struct QuadTransformer {
     transform_matrix: Option<Matrix3<f32>>,
+    /** Cached inverse of `transform_matrix`, kept in step by `set_transform_matrix`;
+    `None` if there is no matrix or it is not invertible */
+    inverse_matrix: Option<Matrix3<f32>>,
     ignore_outside_margin: Option<f32>,
+    src_quad: Option<RectCorners>,
     dst_quad: Option<RectCorners>,
+    fallback_identity: bool,
+    coordinate_system: CoordinateSystem,
+    transform_mode: TransformMode,
+    edge_band: f32,
+    centered_destination: bool,
+    nan_policy: NanPolicy,
+    aspect_mode: AspectMode,
+    not_ready_policy: NotReadyPolicy,
 }
 
 impl QuadTransformer {
@@ -45,23 +221,464 @@ impl QuadTransformer {
             Some(q) => q,
             None => DEFAULT_DST_QUAD,
         };
-        QuadTransformer {
-            transform_matrix: src_quad
-                .map(|quad| build_transform(&quad.clone(), &useable_dst_quad)),
+        let mut transformer = QuadTransformer {
+            transform_matrix: None,
+            inverse_matrix: None,
+            src_quad,
             dst_quad,
             ignore_outside_margin,
+            fallback_identity: false,
+            coordinate_system: CoordinateSystem::default(),
+            transform_mode: TransformMode::default(),
+            edge_band: 0.,
+            centered_destination: false,
+            nan_policy: NanPolicy::default(),
+            aspect_mode: AspectMode::default(),
+            not_ready_policy: NotReadyPolicy::default(),
+        };
+        transformer.set_transform_matrix(
+            src_quad.map(|quad| build_transform(&quad.clone(), &useable_dst_quad)),
+        );
+        transformer
+    }
+
+    /** Replace the transform matrix, updating the cached inverse to match. All
+    changes to the matrix should go through here. */
+    fn set_transform_matrix(&mut self, matrix: Option<Matrix3<f32>>) {
+        self.transform_matrix = matrix;
+        self.inverse_matrix = matrix.and_then(|m| m.try_inverse());
+    }
+
+    /** Equivalent of `new` which returns an error, rather than panicking, if the
+    transform cannot be solved (e.g. for a degenerate source quad). */
+    pub fn checked_new(
+        src_quad: Option<RectCorners>,
+        dst_quad: Option<RectCorners>,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        let mut transformer = QuadTransformer::new(None, dst_quad, ignore_outside_margin);
+        if let Some(src_quad) = src_quad {
+            let matrix = try_build_transform(&src_quad, &transformer.useable_dst_quad())?;
+            transformer.set_transform_matrix(Some(matrix));
+            transformer.src_quad = Some(src_quad);
+        }
+        Ok(transformer)
+    }
+
+    /** Start building a transformer with options beyond those available via `new` */
+    pub fn builder() -> QuadTransformerBuilder {
+        QuadTransformerBuilder::default()
+    }
+
+    /** Build a transformer whose source quad is the whole of an image of the given
+    size, i.e. `(0, 0), (width, 0), (width, height), (0, height)`. */
+    pub fn from_image_size(
+        width: f32,
+        height: f32,
+        dst_quad: RectCorners,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        if !(width > 0. && height > 0.) {
+            return Err(anyhow!("Image size must be positive, got {width}x{height}"));
+        }
+        let src_quad: RectCorners = [(0., 0.), (width, 0.), (width, height), (0., height)];
+        let mut transformer = QuadTransformer::new(None, Some(dst_quad), ignore_outside_margin);
+        transformer.set_transform_matrix(Some(try_build_transform(&src_quad, &dst_quad)?));
+        transformer.src_quad = Some(src_quad);
+        Ok(transformer)
+    }
+
+    /** Build a transformer mapping one arbitrary quad onto another, e.g. two regions
+    measured in the same camera image: `quad_a` is the source, `quad_b` the
+    destination. */
+    pub fn between(
+        quad_a: RectCorners,
+        quad_b: RectCorners,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        QuadTransformer::try_from_quads(quad_a, Some(quad_b), ignore_outside_margin)
+    }
+
+    /** Build a transformer which continues on from `upstream`: its source quad is
+    the upstream destination quad, so the two can be applied in sequence. */
+    pub fn chain_from(
+        upstream: &QuadTransformer,
+        new_dst: RectCorners,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        QuadTransformer::try_from_quads(
+            upstream.useable_dst_quad(),
+            Some(new_dst),
+            ignore_outside_margin,
+        )
+    }
+
+    /** Build a transformer whose destination quad is the source quad scaled by
+    `scale` about its centroid, e.g. to fit the source into a padded version of
+    itself. */
+    pub fn scaled_about_center(
+        src_quad: RectCorners,
+        scale: f32,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        if !(scale.is_finite() && scale != 0.) {
+            return Err(anyhow!("Scale must be finite and non-zero, got {scale}"));
+        }
+        let (cx, cy) = quad_centroid(&src_quad);
+        let dst_quad = src_quad.map(|(x, y)| (cx + scale * (x - cx), cy + scale * (y - cy)));
+        QuadTransformer::try_from_quads(src_quad, Some(dst_quad), ignore_outside_margin)
+    }
+
+    /** Build a transformer mapping the source quad onto the unit square with source
+    corner `origin_corner` (0 to 3, in the usual corner order) at `(0, 0)`, the next
+    corner clockwise at `(1, 0)` and the previous one at `(0, 1)`, for downstream
+    conventions which treat a different corner as the origin. The stored source quad
+    is rotated to start at the chosen corner. */
+    pub fn to_unit_square_from(
+        src_quad: RectCorners,
+        origin_corner: usize,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        if origin_corner >= 4 {
+            return Err(anyhow!("Corner index {origin_corner} is out of range 0..4"));
+        }
+        let rotated = [0, 1, 2, 3].map(|i| src_quad[(origin_corner + i) % 4]);
+        QuadTransformer::try_from_quads(rotated, Some(UNIT_SQUARE), ignore_outside_margin)
+    }
+
+    /** Blend several calibrated transformers, e.g. for multi-zone calibration: the
+    source quad is the weighted average of their source quads (weights are
+    normalized to sum to 1), and the transform is rebuilt against the first
+    transformer's destination quad and options. */
+    pub fn blend(
+        transforms: &[&QuadTransformer],
+        weights: &[f32],
+    ) -> anyhow::Result<QuadTransformer> {
+        if transforms.len() != weights.len() {
+            return Err(anyhow!(
+                "Mismatched lengths: {} transforms, {} weights",
+                transforms.len(),
+                weights.len()
+            ));
+        }
+        let first = transforms
+            .first()
+            .ok_or_else(|| anyhow!("No transforms to blend"))?;
+        if weights.iter().any(|w| !(w.is_finite() && *w >= 0.)) {
+            return Err(anyhow!("Weights must be finite and non-negative"));
+        }
+        let total: f32 = weights.iter().sum();
+        if total <= 0. {
+            return Err(anyhow!("Weights must not all be zero"));
+        }
+
+        let mut blended: RectCorners = [(0., 0.); 4];
+        for (transformer, weight) in transforms.iter().zip(weights.iter()) {
+            let src_quad = transformer
+                .src_quad
+                .ok_or_else(|| anyhow!("Cannot blend a transformer with no source quad"))?;
+            for (corner, src) in blended.iter_mut().zip(src_quad.iter()) {
+                corner.0 += src.0 * weight / total;
+                corner.1 += src.1 * weight / total;
+            }
+        }
+        let mut result = (*first).clone();
+        result.try_set_src_quad(blended)?;
+        Ok(result)
+    }
+
+    /** Build a transformer from a base source quad with each corner moved by the
+    corresponding delta, e.g. for a UI which tracks corner drags relative to an
+    initial quad. */
+    pub fn from_base_with_deltas(
+        base_src: RectCorners,
+        deltas: [Point2D; 4],
+        dst_quad: Option<RectCorners>,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        let mut src_quad = base_src;
+        for (corner, delta) in src_quad.iter_mut().zip(deltas.iter()) {
+            corner.0 += delta.0;
+            corner.1 += delta.1;
+        }
+        QuadTransformer::try_from_quads(src_quad, dst_quad, ignore_outside_margin)
+    }
+
+    /** Build a transformer from source corners given in an unknown order, e.g. from
+    a camera mounted at an angle. The corners are put in clockwise order (on screen),
+    then rotated so that the first edge is the one pointing most nearly along +x,
+    i.e. so they follow the canonical left top, right top, right bottom, left bottom
+    layout. For a quad rotated by close to 45 degrees the choice is ambiguous. */
+    pub fn from_auto_rotated_src(
+        points: [Point2D; 4],
+        dst_quad: Option<RectCorners>,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        let [a, b, c, d] = points;
+        let clockwise = if signed_area(&points) < 0. {
+            [a, d, c, b]
+        } else {
+            points
+        };
+        let horizontalness = |i: usize| {
+            let (start, end) = (clockwise[i], clockwise[(i + 1) % 4]);
+            let length = distance(start.0, start.1, end.0, end.1);
+            if length > 0. {
+                (end.0 - start.0) / length
+            } else {
+                f32::NEG_INFINITY
+            }
+        };
+        let first = (0..4)
+            .max_by(|i, j| horizontalness(*i).total_cmp(&horizontalness(*j)))
+            .expect("a quad has corners");
+        let src_quad = [0, 1, 2, 3].map(|i| clockwise[(first + i) % 4]);
+        QuadTransformer::try_from_quads(src_quad, dst_quad, ignore_outside_margin)
+    }
+
+    /** Build a transformer directly from a known matrix, e.g. one computed
+    elsewhere. No source or destination quad is stored; use `implied_src_quad` and
+    `implied_dst_quad` to get representative quads for display. */
+    pub fn from_matrix(
+        matrix: Matrix3<f32>,
+        ignore_outside_margin: Option<f32>,
+    ) -> QuadTransformer {
+        let mut transformer = QuadTransformer::new(None, None, ignore_outside_margin);
+        transformer.set_transform_matrix(Some(matrix));
+        transformer
+    }
+
+    /** Build an affine transformer from exactly three point correspondences; see
+    `build_affine_transform`. As with `from_matrix`, no quads are stored. */
+    pub fn from_affine_points(
+        src: [Point2D; 3],
+        dst: [Point2D; 3],
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        Ok(QuadTransformer::from_matrix(
+            build_affine_transform(src, dst)?,
+            ignore_outside_margin,
+        ))
+    }
+
+    /** Compose two transformers into one which applies `self` and then `next`, with
+    this transformer's source quad and `next`'s destination quad and options. The
+    matrix is the product of the two, so it is only equivalent to rebuilding from
+    the quads if `self`'s destination quad is `next`'s source quad (as with
+    `chain_from`). The result is not ready unless both are. */
+    pub fn then(&self, next: &QuadTransformer) -> QuadTransformer {
+        let mut composed = next.clone();
+        composed.src_quad = self.src_quad;
+        composed.set_transform_matrix(
+            self.transform_matrix
+                .zip(next.transform_matrix)
+                .map(|(first, second)| second * first),
+        );
+        composed
+    }
+
+    /** Rebuild a transformer from its FFI representation (see `to_ffi`). Only the
+    matrix is carried across, so as with `from_matrix` no quads or options (such as
+    the margin) are restored. */
+    pub fn from_ffi(ffi: &FfiTransform) -> QuadTransformer {
+        let mut transformer = QuadTransformer::new(None, None, None);
+        if ffi.ready {
+            transformer.set_transform_matrix(Some(Matrix3::from_row_slice(&ffi.matrix)));
+        }
+        transformer
+    }
+
+    /** Fallible equivalent of `new` with a source quad */
+    fn try_from_quads(
+        src_quad: RectCorners,
+        dst_quad: Option<RectCorners>,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<QuadTransformer> {
+        let mut builder = QuadTransformer::builder().with_src_quad(src_quad);
+        if let Some(dst_quad) = dst_quad {
+            builder = builder.with_dst_quad(dst_quad);
+        }
+        if let Some(margin) = ignore_outside_margin {
+            builder = builder.with_ignore_outside_margin(margin);
         }
+        builder.build()
     }
 
     pub fn set_new_quad(&mut self, src_quad: &RectCorners, dst_quad: Option<RectCorners>) {
         let useable_dst_quad: RectCorners = match dst_quad {
             Some(q) => q,
-            None => DEFAULT_DST_QUAD,
+            None => default_dst_quad(self.coordinate_system, self.centered_destination),
         };
 
+        self.src_quad = Some(*src_quad);
         self.dst_quad = dst_quad;
 
-        self.transform_matrix = Some(build_transform(src_quad, &useable_dst_quad));
+        let matrix = self.solve(src_quad, &useable_dst_quad).unwrap();
+        self.set_transform_matrix(Some(matrix));
+    }
+
+    /** Partial derivatives of the transformed (x, y) with respect to the source
+    (x, y), evaluated at the given source point */
+    fn jacobian_at(&self, point: &Point2D) -> anyhow::Result<na::Matrix2<f32>> {
+        let m = self
+            .transform_matrix
+            .ok_or_else(|| anyhow!("No transform matrix"))?;
+        let (x, y) = *point;
+        let w = m[(2, 0)] * x + m[(2, 1)] * y + m[(2, 2)];
+        if w == 0. {
+            return Err(anyhow!("Point {x}, {y} maps to infinity"));
+        }
+        let u = (m[(0, 0)] * x + m[(0, 1)] * y + m[(0, 2)]) / w;
+        let v = (m[(1, 0)] * x + m[(1, 1)] * y + m[(1, 2)]) / w;
+        Ok(na::Matrix2::new(
+            (m[(0, 0)] - m[(2, 0)] * u) / w,
+            (m[(0, 1)] - m[(2, 1)] * u) / w,
+            (m[(1, 0)] - m[(2, 0)] * v) / w,
+            (m[(1, 1)] - m[(2, 1)] * v) / w,
+        ))
+    }
+
+    /** The destination quad, or the default 1x1 quad (in the configured coordinate
+    system) if none was set */
+    fn useable_dst_quad(&self) -> RectCorners {
+        self.dst_quad
+            .unwrap_or_else(|| default_dst_quad(self.coordinate_system, self.centered_destination))
+    }
+
+    /** Clear any custom destination quad and rebuild the transform against the
+    default 1x1 quad, using the existing source quad. */
+    pub fn reset_to_default_dst(&mut self) -> anyhow::Result<()> {
+        let src_quad = self.src_quad.ok_or_else(|| anyhow!("No source quad"))?;
+        let default_dst_quad = default_dst_quad(self.coordinate_system, self.centered_destination);
+        let matrix = self.solve(&src_quad, &default_dst_quad)?;
+        self.set_transform_matrix(Some(matrix));
+        self.dst_quad = None;
+        Ok(())
+    }
+
+    /** Scale all transformed output by `factor` about `pivot` (in destination space),
+    by post-multiplying the current transform matrix. The scale is lost if the
+    matrix is later rebuilt, e.g. via `set_new_quad`. */
+    pub fn with_output_scale(&mut self, factor: f32, pivot: Point2D) {
+        match self.transform_matrix {
+            Some(matrix) => {
+                let (px, py) = pivot;
+                let scale = Matrix3::new(
+                    factor,
+                    0.,
+                    px * (1. - factor),
+                    0.,
+                    factor,
+                    py * (1. - factor),
+                    0.,
+                    0.,
+                    1.,
+                );
+                self.set_transform_matrix(Some(scale * matrix));
+            }
+            None => warn!("No transform matrix; output scale ignored"),
+        }
+    }
+
+    /** A copy of this transformer whose output is translated by `offset`, by
+    post-multiplying the matrix rather than rebuilding from the quads, e.g. to follow
+    a projection surface which moves over time. As with `with_output_scale`, the
+    stored destination quad is unchanged, and the offset is lost if the matrix is
+    rebuilt. */
+    pub fn with_dynamic_offset(&self, offset: Point2D) -> QuadTransformer {
+        let mut shifted = self.clone();
+        let translation = Matrix3::new(1., 0., offset.0, 0., 1., offset.1, 0., 0., 1.);
+        shifted.set_transform_matrix(self.transform_matrix.map(|m| translation * m));
+        shifted
+    }
+
+    /** Scale all input points by `(sx, sy)` before they are transformed, by
+    pre-multiplying the current transform matrix. This allows e.g. a source quad
+    calibrated in pixels to accept normalized input. As with `with_output_scale`,
+    the scale is lost if the matrix is later rebuilt. */
+    pub fn with_input_scale(&mut self, sx: f32, sy: f32) {
+        match self.transform_matrix {
+            Some(matrix) => {
+                let scale = Matrix3::new(sx, 0., 0., 0., sy, 0., 0., 0., 1.);
+                self.set_transform_matrix(Some(matrix * scale));
+            }
+            None => warn!("No transform matrix; input scale ignored"),
+        }
+    }
+
+    /** A copy of this transformer with one source corner (0 to 3, in the usual
+    corner order) moved by `delta`, and the matrix rebuilt accordingly. Useful for
+    optimisers which nudge corner positions. */
+    pub fn with_perturbed_corner(
+        &self,
+        index: usize,
+        delta: Point2D,
+    ) -> anyhow::Result<QuadTransformer> {
+        let mut src_quad = self.src_quad.ok_or_else(|| anyhow!("No source quad"))?;
+        let corner = src_quad
+            .get_mut(index)
+            .ok_or_else(|| anyhow!("Corner index {index} is out of range 0..4"))?;
+        corner.0 += delta.0;
+        corner.1 += delta.1;
+
+        let mut perturbed = self.clone();
+        perturbed.set_transform_matrix(Some(self.solve(&src_quad, &self.useable_dst_quad())?));
+        perturbed.src_quad = Some(src_quad);
+        Ok(perturbed)
+    }
+
+    /** Round every element of the transform matrix to `decimals` decimal places, so
+    that transforms saved and reloaded on different platforms compare exactly.
+
+    This deliberately loses precision: the perspective elements in particular are
+    often very small (e.g. 1e-4 for pixel-sized quads), so too few decimals can
+    noticeably change the transform. Check the results against your own tolerance.
+    Elements for which `decimals` exceeds what an `f32` can hold are left as they
+    are. */
+    pub fn quantize_matrix(&mut self, decimals: u32) {
+        // 10^308 is the largest power of ten an f64 can represent
+        let factor = 10_f64.powi(decimals.min(308) as i32);
+        let quantize = |value: f32| {
+            let scaled = value as f64 * factor;
+            if scaled.is_finite() {
+                (scaled.round() / factor) as f32
+            } else {
+                value
+            }
+        };
+        let quantized = self.transform_matrix.map(|matrix| matrix.map(quantize));
+        self.set_transform_matrix(quantized);
+    }
+
+    /** Replace the source quad and rebuild against the current destination quad,
+    leaving the transformer unchanged if that fails */
+    fn try_set_src_quad(&mut self, src_quad: RectCorners) -> anyhow::Result<()> {
+        let matrix = self.solve(&src_quad, &self.useable_dst_quad())?;
+        self.set_transform_matrix(Some(matrix));
+        self.src_quad = Some(src_quad);
+        Ok(())
+    }
+
+    /** Build the transform matrix (from the region of the source quad chosen by the
+    `AspectMode`), installing identity instead of failing if the fallback was enabled
+    via `QuadTransformerBuilder::with_fallback_identity` */
+    fn solve(
+        &self,
+        src_quad: &RectCorners,
+        dst_quad: &RectCorners,
+    ) -> anyhow::Result<Matrix3<f32>> {
+        let src_region = match self.aspect_mode {
+            AspectMode::Stretch => *src_quad,
+            AspectMode::Fill => aspect_fill_region(src_quad, dst_quad),
+        };
+        match try_build_transform(&src_region, dst_quad) {
+            Ok(matrix) => Ok(matrix),
+            Err(e) if self.fallback_identity => {
+                warn!("Failed to build transform ({e}); falling back to identity");
+                Ok(Matrix3::identity())
+            }
+            Err(e) => Err(e),
+        }
     }
 
     /** Take a single input point (within the source quad) and return the
@@ -69,97 +686,2327 @@ impl QuadTransformer {
     pub fn transform(&self, point: &Point2D) -> anyhow::Result<Point2D> {
         match self.transform_matrix {
             Some(matrix) => {
+                if self.transform_mode == TransformMode::FrontOnly {
+                    let (_, _, w) = self.transform_homogeneous(point)?;
+                    if w.abs() <= f32::EPSILON {
+                        return Err(anyhow!("Point {point:?} maps to infinity"));
+                    }
+                    if w < 0. {
+                        return Err(anyhow!("Point {point:?} is behind the projection plane"));
+                    }
+                }
                 let (x, y) = point;
                 let nalgebra_point = Point2::new(*x, *y);
 
                 let transformed = matrix.transform_point(&nalgebra_point);
                 Ok((transformed.x, transformed.y))
             }
-            None => Err(anyhow!("No transform matrix")),
+            None => match self.not_ready_policy {
+                NotReadyPolicy::Error => Err(anyhow!("No transform matrix")),
+                NotReadyPolicy::Passthrough => Ok(*point),
+            },
         }
     }
 
-    /** Using the `ignore_outside_margin` value (if set), return only the points that are
-    deemed to be "inside the destination quad". */
-    pub fn filter_points_inside(&self, points: &[Point2D]) -> anyhow::Result<Vec<Point2D>> {
-        let points: Vec<Point2D> = points
+    /** Apply the transform matrix to the point in homogeneous coordinates, returning
+    `(x, y, w)` *before* the perspective divide. */
+    pub fn transform_homogeneous(&self, point: &Point2D) -> anyhow::Result<(f32, f32, f32)> {
+        let matrix = self
+            .transform_matrix
+            .ok_or_else(|| anyhow!("No transform matrix"))?;
+        let transformed = matrix * na::Vector3::new(point.0, point.1, 1.);
+        Ok((transformed.x, transformed.y, transformed.z))
+    }
+
+    /** Transform a point given in logical (DPI-independent) screen units, for a
+    source quad calibrated in physical pixels: the point is multiplied by
+    `dpi_scale` before being transformed. */
+    pub fn transform_screen(&self, point: &Point2D, dpi_scale: f32) -> anyhow::Result<Point2D> {
+        self.transform(&(point.0 * dpi_scale, point.1 * dpi_scale))
+    }
+
+    /** Transform a point, as per `transform`, and snap the result to integer pixel
+    coordinates using the given rounding strategy. */
+    pub fn transform_to_pixels(
+        &self,
+        point: &Point2D,
+        rounding: PixelRounding,
+    ) -> anyhow::Result<(i32, i32)> {
+        let (x, y) = self.transform(point)?;
+        Ok((rounding.apply(x), rounding.apply(y)))
+    }
+
+    /** Transform a point and return the result in signed Q16.16 fixed point: each
+    `i32` holds the value multiplied by 2^16 and rounded to the nearest integer, so
+    the top 16 bits are the integer part (two's complement) and the bottom 16 bits
+    the fraction. This gives a resolution of 1/65536 over the range
+    [-32768, 32768); results outside that range are an error. */
+    pub fn transform_fixed(&self, point: &Point2D) -> anyhow::Result<(i32, i32)> {
+        const ONE: f64 = (1 << 16) as f64;
+        let to_fixed = |value: f32| {
+            let fixed = (value as f64 * ONE).round();
+            if fixed >= i32::MIN as f64 && fixed <= i32::MAX as f64 {
+                Ok(fixed as i32)
+            } else {
+                Err(anyhow!("{value} is out of range for Q16.16"))
+            }
+        };
+        let (x, y) = self.transform(point)?;
+        Ok((to_fixed(x)?, to_fixed(y)?))
+    }
+
+    /** Transform every point in the slice, as per `transform`. Points with a NaN or
+    infinite coordinate are handled according to the `NanPolicy` (by default they
+    are skipped, so the result may be shorter than the input). */
+    pub fn transform_many(&self, points: &[Point2D]) -> anyhow::Result<Vec<Point2D>> {
+        self.check_ready()?;
+        self.apply_nan_policy(points)?
             .iter()
-            .filter(|point| match self.ignore_outside_margin {
-                Some(margin) => {
-                    let (x, y) = point;
-                    debug!("...Is {x}, {y} outside of {margin}?");
-                    if let Some(dst_quad) = self.dst_quad {
-                        let [a, b, c, d] = dst_quad;
-                        let d_width = distance(a.0, a.1, b.0, b.1);
-                        let d_height = distance(a.0, a.1, d.0, d.1);
-                        !(*x > (d_width + margin)
-                            || *x < (0. - margin)
-                            || *y > (d_height + margin)
-                            || *y < (0. - margin))
-                    } else {
-                        // No destination quad set; use "default" [0;1]
-                        !(*x > (DST_SIZE + margin)
-                            || *x < (0. - margin)
-                            || *y > (DST_SIZE + margin)
-                            || *y < (0. - margin))
-                    }
+            .map(|point| self.transform(point))
+            .collect()
+    }
+
+    /** Same as `transform_many`, but also returns each point's homogeneous w (before
+    the perspective divide; see `transform_homogeneous`), e.g. to drive depth or fog
+    effects. Points passed through by `NotReadyPolicy::Passthrough` have a w of 1. */
+    pub fn transform_many_with_w(&self, points: &[Point2D]) -> anyhow::Result<Vec<(Point2D, f32)>> {
+        self.check_ready()?;
+        self.apply_nan_policy(points)?
+            .iter()
+            .map(|point| {
+                let w = match self.transform_matrix {
+                    Some(_) => self.transform_homogeneous(point)?.2,
+                    None => 1.,
+                };
+                Ok((self.transform(point)?, w))
+            })
+            .collect()
+    }
+
+    /** For batch methods, which would otherwise fail on their first point: an error
+    if there is no transform matrix and the `NotReadyPolicy` is `Error`. Under
+    `NotReadyPolicy::Passthrough`, `transform` then returns each point unchanged. */
+    fn check_ready(&self) -> anyhow::Result<()> {
+        if !self.is_ready() && self.not_ready_policy == NotReadyPolicy::Error {
+            return Err(anyhow!("No transform matrix"));
+        }
+        Ok(())
+    }
+
+    /** The points which batch methods should transform, as per the `NanPolicy` */
+    fn apply_nan_policy<'a>(
+        &self,
+        points: &'a [Point2D],
+    ) -> anyhow::Result<std::borrow::Cow<'a, [Point2D]>> {
+        use std::borrow::Cow;
+
+        let is_finite = |(x, y): &&Point2D| x.is_finite() && y.is_finite();
+        match self.nan_policy {
+            NanPolicy::PassThrough | NanPolicy::Fail => {
+                self.check_nan_policy(points)?;
+                Ok(Cow::Borrowed(points))
+            }
+            NanPolicy::Skip => {
+                if points.iter().all(|p| is_finite(&p)) {
+                    return Ok(Cow::Borrowed(points));
+                }
+                let finite: Vec<Point2D> = points.iter().filter(is_finite).copied().collect();
+                warn!(
+                    "Skipped {} non-finite point(s) of {}",
+                    points.len() - finite.len(),
+                    points.len()
+                );
+                Ok(Cow::Owned(finite))
+            }
+        }
+    }
+
+    /** For batch methods whose results must line up with their input, so points
+    can't be skipped: an error under `NanPolicy::Fail` if any point has a NaN or
+    infinite coordinate, otherwise nothing. */
+    fn check_nan_policy(&self, points: &[Point2D]) -> anyhow::Result<()> {
+        if self.nan_policy != NanPolicy::Fail {
+            return Ok(());
+        }
+        match points
+            .iter()
+            .position(|(x, y)| !x.is_finite() || !y.is_finite())
+        {
+            Some(index) => Err(anyhow!("Point {index} ({:?}) is not finite", points[index])),
+            None => Ok(()),
+        }
+    }
+
+    /** Transform the points and count them into a `bins.0` x `bins.1` (columns x
+    rows) grid covering the destination quad's bounding box. The counts are returned
+    row-major, i.e. index `row * columns + column`. Points landing outside the
+    bounding box, or on a NaN coordinate (e.g. with `NanPolicy::PassThrough`), are
+    not counted. */
+    pub fn accumulate_heatmap(
+        &self,
+        points: &[Point2D],
+        bins: (usize, usize),
+    ) -> anyhow::Result<Vec<u32>> {
+        let (columns, rows) = bins;
+        if columns == 0 || rows == 0 {
+            return Err(anyhow!("Heatmap needs at least one bin in each direction"));
+        }
+        let (min, max) = bounds(&self.useable_dst_quad());
+        let (width, height) = (max.0 - min.0, max.1 - min.1);
+        let mut heatmap = vec![0; columns * rows];
+        for (x, y) in self.transform_many(points)? {
+            // Written so that NaN coordinates also fail the check
+            if !(x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1) {
+                continue;
+            }
+            let bin = |offset: f32, size: f32, count: usize| {
+                ((offset / size * count as f32) as usize).min(count - 1)
+            };
+            let (column, row) = (bin(x - min.0, width, columns), bin(y - min.1, height, rows));
+            heatmap[row * columns + column] += 1;
+        }
+        Ok(heatmap)
+    }
+
+    /** Transform a fixed-size array of points without allocating. As with
+    `transform_slice_in_place`, non-finite points can't be skipped, so
+    `NanPolicy::Skip` transforms them like `NanPolicy::PassThrough`. */
+    pub fn transform_array<const N: usize>(
+        &self,
+        points: &[Point2D; N],
+    ) -> anyhow::Result<[Point2D; N]> {
+        let mut result = *points;
+        self.transform_slice_in_place(&mut result)?;
+        Ok(result)
+    }
+
+    /** Transform a list of named anchor points, keeping each name alongside its
+    transformed position. */
+    pub fn transform_named(
+        &self,
+        anchors: &[(String, Point2D)],
+    ) -> anyhow::Result<Vec<(String, Point2D)>> {
+        anchors
+            .iter()
+            .map(|(name, point)| Ok((name.clone(), self.transform(point)?)))
+            .collect()
+    }
+
+    /** Transform only those points which lie within the source quad (or within
+    `margin` distance outside of it), returning each transformed point along with
+    its index in the input. Points with a NaN or infinite coordinate are left out,
+    unless the `NanPolicy` is `Fail`. */
+    pub fn transform_in_source_region(
+        &self,
+        points: &[Point2D],
+        margin: f32,
+    ) -> anyhow::Result<Vec<(usize, Point2D)>> {
+        self.check_ready()?;
+        self.check_nan_policy(points)?;
+        points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| self.src_contains(point, margin))
+            .map(|(i, point)| Ok((i, self.transform(point)?)))
+            .collect()
+    }
+
+    /** Transform all the points, returning only those which land inside the
+    destination quad (as per `filter_points_inside`), each along with its index in
+    the input. Points with a NaN or infinite coordinate are left out (they can't be
+    inside), unless the `NanPolicy` is `Fail`. */
+    pub fn transform_inside_indexed(
+        &self,
+        points: &[Point2D],
+    ) -> anyhow::Result<Vec<(usize, Point2D)>> {
+        self.check_ready()?;
+        self.check_nan_policy(points)?;
+        let mut inside = Vec::new();
+        for (i, point) in points.iter().enumerate() {
+            if !point.0.is_finite() || !point.1.is_finite() {
+                continue;
+            }
+            let transformed = self.transform(point)?;
+            if self.point_is_inside_quad(&transformed) {
+                inside.push((i, transformed));
+            }
+        }
+        Ok(inside)
+    }
+
+    /** Move the transformer onto a background thread which transforms each point
+    received on `rx` and sends the result on `tx`, until either channel is closed. */
+    #[cfg(feature = "threads")]
+    pub fn spawn_transform_worker(
+        self,
+        rx: std::sync::mpsc::Receiver<Point2D>,
+        tx: std::sync::mpsc::Sender<anyhow::Result<Point2D>>,
+    ) -> std::thread::JoinHandle<()> {
+        std::thread::spawn(move || {
+            for point in rx {
+                if tx.send(self.transform(&point)).is_err() {
+                    break;
                 }
-                None => true,
+            }
+        })
+    }
+
+    /** Transform the point and locate it in a `grid.0` x `grid.1` (columns x rows)
+    grid laid across the destination quad in its own bilinear coordinates, so cells
+    follow any skew of the quad. Returns the (column, row) of the cell, or `None` if
+    the point lands outside the destination quad. */
+    pub fn to_local_cell(
+        &self,
+        point: &Point2D,
+        grid: (usize, usize),
+    ) -> anyhow::Result<Option<(usize, usize)>> {
+        let (columns, rows) = grid;
+        if columns == 0 || rows == 0 {
+            return Err(anyhow!(
+                "Grid must have at least one cell, got {columns}x{rows}"
+            ));
+        }
+        let transformed = self.transform(point)?;
+        let cell = bilinear_inverse(&self.useable_dst_quad(), transformed)
+            .filter(|(u, v)| (0. ..=1.).contains(u) && (0. ..=1.).contains(v))
+            .map(|(u, v)| {
+                (
+                    ((u * columns as f32) as usize).min(columns - 1),
+                    ((v * rows as f32) as usize).min(rows - 1),
+                )
+            });
+        Ok(cell)
+    }
+
+    /** Transform the point, then clamp the result to the destination quad's
+    axis-aligned bounding box, e.g. for indexing strictly within an output buffer.
+    Note that for a non-rectangular destination quad, clamped points may still lie
+    outside the quad itself; see `transform_clamped_src` for that. */
+    pub fn transform_clamped_dst(&self, point: &Point2D) -> anyhow::Result<Point2D> {
+        let (x, y) = self.transform(point)?;
+        let (min, max) = bounds(&self.useable_dst_quad());
+        Ok((x.clamp(min.0, max.0), y.clamp(min.1, max.1)))
+    }
+
+    /** Transform the point, returning the result only if it lands inside `mask_quad`
+    (or within `margin` of its edges; see `point_in_quad`), e.g. an inset content
+    region of the destination surface. */
+    pub fn transform_masked(
+        &self,
+        point: &Point2D,
+        mask_quad: &RectCorners,
+        margin: f32,
+    ) -> anyhow::Result<Option<Point2D>> {
+        let transformed = self.transform(point)?;
+        Ok(point_in_quad(mask_quad, &transformed, margin).then_some(transformed))
+    }
+
+    /** Transform `segments` points evenly spaced around the (source space) circle,
+    starting at angle zero, giving a polygon approximating the ellipse it becomes
+    (at least locally; a strong perspective distorts it further). */
+    pub fn transform_circle(
+        &self,
+        center: Point2D,
+        radius: f32,
+        segments: usize,
+    ) -> anyhow::Result<Vec<Point2D>> {
+        if segments < 3 {
+            return Err(anyhow!(
+                "A circle needs at least 3 segments, got {segments}"
+            ));
+        }
+        (0..segments)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / segments as f32;
+                self.transform(&(
+                    center.0 + radius * angle.cos(),
+                    center.1 + radius * angle.sin(),
+                ))
             })
-            .map(|p| (p.0, p.1))
-            .collect();
-        Ok(points)
+            .collect()
     }
 
-    pub fn is_ready(&self) -> bool {
-        self.transform_matrix.is_some()
+    /** Transform the point after first clamping it to the source quad, i.e. moving
+    it to the nearest point on the quad boundary if it lies outside, so that the
+    result never lands outside the destination quad. Requires a source quad. */
+    pub fn transform_clamped_src(&self, point: &Point2D) -> anyhow::Result<Point2D> {
+        let src_quad = self.src_quad.ok_or_else(|| anyhow!("No source quad"))?;
+        if point_in_quad(&src_quad, point, 0.) {
+            self.transform(point)
+        } else {
+            self.transform(&closest_point_on_quad_edges(&src_quad, point))
+        }
     }
-}
 
-fn distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
-    ((x2 - x1).powf(2.0) + (y2 - y1).powf(2.0)).sqrt()
-}
+    /** Transform every point in the slice in place, overwriting each with its
+    transformed value. The slice is left untouched if the transformer is not ready
+    (an error unless the `NotReadyPolicy` is `Passthrough`), but with
+    `TransformMode::FrontOnly` an error partway through leaves the points before it
+    transformed. Non-finite points can't be skipped here, so `NanPolicy::Skip`
+    transforms them like `NanPolicy::PassThrough`, while `NanPolicy::Fail` checks
+    every point before any are overwritten. */
+    pub fn transform_slice_in_place(&self, points: &mut [Point2D]) -> anyhow::Result<()> {
+        self.check_ready()?;
+        self.check_nan_policy(points)?;
+        for point in points.iter_mut() {
+            *point = self.transform(point)?;
+        }
+        Ok(())
+    }
 
-fn build_transform(src_quad: &RectCorners, dst_quad: &RectCorners) -> Matrix3<f32> {
-    // Mappings by row - each should have 8 terms
+    /** Same as `transform_many`, but processes four points per iteration using SIMD
+    lanes (via the `wide` crate, which falls back to scalar code on targets without
+    SIMD support). Results agree with `transform_many` to within floating point
+    rounding. With `TransformMode::FrontOnly`, or before the transformer is ready,
+    this simply calls `transform_many`. */
+    #[cfg(feature = "simd")]
+    pub fn transform_many_simd(&self, points: &[Point2D]) -> anyhow::Result<Vec<Point2D>> {
+        use wide::{f32x4, CmpEq};
 
-    let r1: [f32; 8] = [
-        src_quad[0].0,
-        src_quad[0].1,
-        1.,
-        0.,
-        0.,
-        0.,
-        -src_quad[0].0 * dst_quad[0].0,
-        -src_quad[0].1 * dst_quad[0].0,
-    ];
-    let r2: [f32; 8] = [
-        0.,
-        0.,
-        0.,
-        src_quad[0].0,
-        src_quad[0].1,
-        1.,
-        -src_quad[0].0 * dst_quad[0].1,
-        -src_quad[0].1 * dst_quad[0].1,
-    ];
-    let r3: [f32; 8] = [
-        src_quad[1].0,
-        src_quad[1].1,
-        1.,
-        0.,
-        0.,
-        0.,
-        -src_quad[1].0 * dst_quad[1].0,
-        -src_quad[1].1 * dst_quad[1].0,
-    ];
-    let r4: [f32; 8] = [
-        0.,
-        0.,
-        0.,
-        src_quad[1].0,
-        src_quad[1].1,
-        1.,
+        let matrix = match self.transform_matrix {
+            Some(matrix) if self.transform_mode != TransformMode::FrontOnly => matrix,
+            // Not ready (as per the `NotReadyPolicy`) or FrontOnly
+            _ => return self.transform_many(points),
+        };
+        let points = &*self.apply_nan_policy(points)?;
+        let m = |row: usize, col: usize| f32x4::splat(matrix[(row, col)]);
+        let (m00, m01, m02) = (m(0, 0), m(0, 1), m(0, 2));
+        let (m10, m11, m12) = (m(1, 0), m(1, 1), m(1, 2));
+        let (m20, m21, m22) = (m(2, 0), m(2, 1), m(2, 2));
+
+        let mut result = Vec::with_capacity(points.len());
+        let chunks = points.chunks_exact(4);
+        let remainder = chunks.remainder();
+        for chunk in chunks {
+            let x = f32x4::from([chunk[0].0, chunk[1].0, chunk[2].0, chunk[3].0]);
+            let y = f32x4::from([chunk[0].1, chunk[1].1, chunk[2].1, chunk[3].1]);
+            let u = m00 * x + m01 * y + m02;
+            let v = m10 * x + m11 * y + m12;
+            let w = m20 * x + m21 * y + m22;
+            // As with nalgebra's transform_point, skip the division where w is zero
+            let w = w.cmp_eq(f32x4::ZERO).blend(f32x4::ONE, w);
+            let (xs, ys) = ((u / w).to_array(), (v / w).to_array());
+            result.extend(xs.into_iter().zip(ys));
+        }
+        result.extend(self.transform_many(remainder)?);
+
+        Ok(result)
+    }
+
+    /** Transform the point, as per `transform`, and convert the result to half
+    precision, e.g. for a bandwidth-limited link. `f16` has an 11 bit significand,
+    so the result is only accurate to about 1 part in 2000 of its magnitude (e.g.
+    to within 0.25 for values around 1000), and values beyond ±65504 overflow to
+    infinity. */
+    #[cfg(feature = "half")]
+    pub fn transform_f16(&self, point: &Point2D) -> anyhow::Result<(half::f16, half::f16)> {
+        let (x, y) = self.transform(point)?;
+        Ok((half::f16::from_f32(x), half::f16::from_f32(y)))
+    }
+
+    /** Transform all the points, then merge any that land within `radius` distance
+    of each other (in destination space) into a single point at their average
+    position. Points are merged greedily, in input order. */
+    pub fn transform_and_dedup(
+        &self,
+        points: &[Point2D],
+        radius: f32,
+    ) -> anyhow::Result<Vec<Point2D>> {
+        // Running (sum x, sum y, count) for each merged point
+        let mut clusters: Vec<(f32, f32, usize)> = Vec::new();
+        for (x, y) in self.transform_many(points)? {
+            let existing = clusters.iter_mut().find(|(sum_x, sum_y, count)| {
+                let n = *count as f32;
+                distance(x, y, sum_x / n, sum_y / n) <= radius
+            });
+            match existing {
+                Some(cluster) => {
+                    cluster.0 += x;
+                    cluster.1 += y;
+                    cluster.2 += 1;
+                }
+                None => clusters.push((x, y, 1)),
+            }
+        }
+        Ok(clusters
+            .into_iter()
+            .map(|(sum_x, sum_y, count)| (sum_x / count as f32, sum_y / count as f32))
+            .collect())
+    }
+
+    /** Transform the polygon's vertices and clip the result to the (convex)
+    destination quad, using the Sutherland-Hodgman algorithm. The returned
+    polygon is empty if it lies entirely outside the destination quad. Dropping a
+    vertex would change the polygon's shape, so the `NanPolicy` does not apply:
+    any vertex which is, or maps to, a NaN or infinite point is an error. */
+    pub fn transform_polygon_clipped(&self, polygon: &[Point2D]) -> anyhow::Result<Vec<Point2D>> {
+        let transformed = polygon
+            .iter()
+            .enumerate()
+            .map(|(i, vertex)| {
+                let (x, y) = self.transform(vertex)?;
+                if x.is_finite() && y.is_finite() {
+                    Ok((x, y))
+                } else {
+                    Err(anyhow!(
+                        "Vertex {i} ({vertex:?}) does not map to a finite point"
+                    ))
+                }
+            })
+            .collect::<anyhow::Result<Vec<Point2D>>>()?;
+        Ok(clip_polygon(&transformed, &self.useable_dst_quad()))
+    }
+
+    /** The local magnification of the transform along each destination edge (in
+    the order of `quad_edge_midpoints`), evaluated at the edge midpoint: how much a
+    short step along the corresponding source edge is stretched, e.g. to size an edge
+    feather consistently in source units. */
+    pub fn edge_scales(&self) -> anyhow::Result<[f32; 4]> {
+        let src_corners = self.dst_corners_in_source()?;
+        let midpoints = quad_edge_midpoints(&self.useable_dst_quad());
+        let mut scales = [0.; 4];
+        for (i, scale) in scales.iter_mut().enumerate() {
+            let (a, b) = (src_corners[i], src_corners[(i + 1) % 4]);
+            let length = distance(a.0, a.1, b.0, b.1);
+            if length <= 0. {
+                return Err(anyhow!(
+                    "Destination edge {i} has zero length in source space"
+                ));
+            }
+            let tangent = na::Vector2::new((b.0 - a.0) / length, (b.1 - a.1) / length);
+            let midpoint = self.inverse_transform(&midpoints[i])?;
+            *scale = (self.jacobian_at(&midpoint)? * tangent).norm();
+        }
+        Ok(scales)
+    }
+
+    /** The local magnification of the transform along the source x and y axes at
+    the given (source space) point, i.e. the lengths of the columns of the
+    transform's Jacobian there. */
+    pub fn axis_scales_at(&self, point: &Point2D) -> anyhow::Result<(f32, f32)> {
+        let jacobian = self.jacobian_at(point)?;
+        Ok((jacobian.column(0).norm(), jacobian.column(1).norm()))
+    }
+
+    /** Transform a vector (e.g. a velocity) positioned at `base_point` in source
+    space. Only the local linear part of the transform (its Jacobian at
+    `base_point`) is applied, so there is no translation. */
+    pub fn transform_vector_at(
+        &self,
+        base_point: &Point2D,
+        vector: &Point2D,
+    ) -> anyhow::Result<Point2D> {
+        let transformed = self.jacobian_at(base_point)? * na::Vector2::new(vector.0, vector.1);
+        Ok((transformed.x, transformed.y))
+    }
+
+    /** Transform the four corners of the source quad. For a correctly built transform
+    these match the destination quad (within floating point tolerance). */
+    pub fn transformed_corners(&self) -> anyhow::Result<RectCorners> {
+        let src_quad = self.src_quad.ok_or_else(|| anyhow!("No source quad"))?;
+        let [a, b, c, d] = src_quad;
+        Ok([
+            self.transform(&a)?,
+            self.transform(&b)?,
+            self.transform(&c)?,
+            self.transform(&d)?,
+        ])
+    }
+
+    /** Transform the centroid (average of the four corners) of the source quad.
+    Because the transform is projective, this is generally *not* the same as the
+    centroid of the destination quad. */
+    pub fn transformed_center(&self) -> anyhow::Result<Point2D> {
+        let src_quad = self.src_quad.ok_or_else(|| anyhow!("No source quad"))?;
+        self.transform(&quad_centroid(&src_quad))
+    }
+
+    /** Take a point expressed as fractional `(u, v)` coordinates across the source
+    quad (`(0, 0)` is the "left top" corner, `(1, 1)` the "right bottom") and return the
+    transformed result within the destination quad. The fractional coordinates are
+    mapped bilinearly onto the source quad before the transform is applied. */
+    pub fn transform_normalized(&self, uv: Point2D) -> anyhow::Result<Point2D> {
+        match self.src_quad {
+            Some(src_quad) => self.transform(&bilinear_point(&src_quad, uv)),
+            None => Err(anyhow!("No source quad")),
+        }
+    }
+
+    /** Sample the transform on a regular `cols` x `rows` grid spanning the source
+    quad's parameter space (see `transform_normalized`), e.g. for uploading as a
+    displacement map. The result is row-major with x and y interleaved, so the
+    sample at (col, row) is at index `2 * (row * cols + col)`; the first and last
+    rows and columns lie on the quad edges. Needs at least 2 columns and rows. */
+    pub fn sample_grid(&self, cols: usize, rows: usize) -> anyhow::Result<Vec<f32>> {
+        check_grid_size(cols, rows)?;
+        let mut samples = Vec::with_capacity(cols * rows * 2);
+        for row in 0..rows {
+            let v = row as f32 / (rows - 1) as f32;
+            for col in 0..cols {
+                let u = col as f32 / (cols - 1) as f32;
+                let (x, y) = self.transform_normalized((u, v))?;
+                samples.push(x);
+                samples.push(y);
+            }
+        }
+        Ok(samples)
+    }
+
+    /** A coarse summary of the warp for QA: for each point of the same grid as
+    `sample_grid` (row-major), the source point, its transformed point, and the
+    displacement from one to the other (transformed minus source). */
+    pub fn warp_field(
+        &self,
+        cols: usize,
+        rows: usize,
+    ) -> anyhow::Result<Vec<(Point2D, Point2D, Point2D)>> {
+        let src_quad = self.src_quad.ok_or_else(|| anyhow!("No source quad"))?;
+        check_grid_size(cols, rows)?;
+        let mut field = Vec::with_capacity(cols * rows);
+        for row in 0..rows {
+            let v = row as f32 / (rows - 1) as f32;
+            for col in 0..cols {
+                let u = col as f32 / (cols - 1) as f32;
+                let src = bilinear_point(&src_quad, (u, v));
+                let dst = self.transform(&src)?;
+                field.push((src, dst, (dst.0 - src.0, dst.1 - src.1)));
+            }
+        }
+        Ok(field)
+    }
+
+    /** Map a point from the destination quad back into the source quad, i.e. the
+    inverse of `transform`. */
+    pub fn inverse_transform(&self, point: &Point2D) -> anyhow::Result<Point2D> {
+        if !self.is_ready() {
+            return Err(anyhow!("No transform matrix"));
+        }
+        let inverse = self
+            .inverse_matrix
+            .ok_or_else(|| anyhow!("Transform matrix is not invertible"))?;
+        let transformed = inverse.transform_point(&Point2::new(point.0, point.1));
+        Ok((transformed.x, transformed.y))
+    }
+
+    /** Map every point in the slice back into the source quad, as per
+    `inverse_transform`. */
+    pub fn inverse_transform_many(&self, points: &[Point2D]) -> anyhow::Result<Vec<Point2D>> {
+        let mut out = Vec::with_capacity(points.len());
+        self.inverse_transform_into(points, &mut out)?;
+        Ok(out)
+    }
+
+    /** Same as `inverse_transform_many`, but writes the results into `out`
+    (replacing its contents), so a buffer can be reused from frame to frame without
+    reallocating. `out` is left untouched on error. */
+    pub fn inverse_transform_into(
+        &self,
+        points: &[Point2D],
+        out: &mut Vec<Point2D>,
+    ) -> anyhow::Result<()> {
+        if !self.is_ready() {
+            return Err(anyhow!("No transform matrix"));
+        }
+        let inverse = self
+            .inverse_matrix
+            .ok_or_else(|| anyhow!("Transform matrix is not invertible"))?;
+        out.clear();
+        out.extend(points.iter().map(|(x, y)| {
+            let p = inverse.transform_point(&Point2::new(*x, *y));
+            (p.x, p.y)
+        }));
+        Ok(())
+    }
+
+    /** Inverse transform the four corners of the destination quad into source space.
+    For a correctly built transform these match the source quad (within floating
+    point tolerance); the reverse of `transformed_corners`. */
+    pub fn dst_corners_in_source(&self) -> anyhow::Result<RectCorners> {
+        let [a, b, c, d] = self.useable_dst_quad();
+        Ok([
+            self.inverse_transform(&a)?,
+            self.inverse_transform(&b)?,
+            self.inverse_transform(&c)?,
+            self.inverse_transform(&d)?,
+        ])
+    }
+
+    /** The destination quad's edges inverse transformed into source space, each as
+    a polyline of `samples_per_edge` points evenly spaced along the edge (in
+    destination space) from one corner to the next, e.g. for drawing where the
+    projection edges fall on a camera feed. Edges are in the order of
+    `quad_edge_midpoints`. */
+    pub fn dst_edges_in_source(
+        &self,
+        samples_per_edge: usize,
+    ) -> anyhow::Result<Vec<Vec<Point2D>>> {
+        if samples_per_edge < 2 {
+            return Err(anyhow!(
+                "Each edge needs at least 2 samples, got {samples_per_edge}"
+            ));
+        }
+        let dst_quad = self.useable_dst_quad();
+        (0..4)
+            .map(|i| {
+                let (a, b) = (dst_quad[i], dst_quad[(i + 1) % 4]);
+                (0..samples_per_edge)
+                    .map(|s| {
+                        let t = s as f32 / (samples_per_edge - 1) as f32;
+                        self.inverse_transform(&(a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1)))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /** Map fractional `(u, v)` coordinates across the source quad into the destination
+    quad. Equivalent to `transform_normalized`; see `dst_to_uv` for the inverse. */
+    pub fn uv_to_dst(&self, uv: Point2D) -> anyhow::Result<Point2D> {
+        self.transform_normalized(uv)
+    }
+
+    /** Same as `uv_to_dst`, but first wraps `u` and `v` into [0, 1) (modulo 1), so
+    coordinates outside the source quad repeat it as a tile rather than extrapolating
+    beyond it. */
+    pub fn transform_uv_wrapped(&self, uv: Point2D) -> anyhow::Result<Point2D> {
+        self.uv_to_dst((uv.0.rem_euclid(1.), uv.1.rem_euclid(1.)))
+    }
+
+    /** Map a point in the destination quad back to fractional `(u, v)` coordinates
+    across the source quad, by inverse transforming into source space and then
+    inverting the bilinear mapping used by `uv_to_dst`. */
+    pub fn dst_to_uv(&self, point: Point2D) -> anyhow::Result<Point2D> {
+        let src_quad = self.src_quad.ok_or_else(|| anyhow!("No source quad"))?;
+        let src_point = self.inverse_transform(&point)?;
+        bilinear_inverse(&src_quad, src_point)
+            .ok_or_else(|| anyhow!("Point cannot be expressed in source quad coordinates"))
+    }
+
+    /** Using the `ignore_outside_margin` value (if set), return only the points that are
+    deemed to be "inside the destination quad".
+
+    Note that this only depends on the destination quad and margin, not on the
+    transform matrix, so it will happily filter points even if the transformer is
+    not yet ready. Use `filter_points_inside_checked` to treat that as an error. */
+    pub fn filter_points_inside(&self, points: &[Point2D]) -> anyhow::Result<Vec<Point2D>> {
+        let points: Vec<Point2D> = points
+            .iter()
+            .filter(|point| self.point_is_inside_quad(point))
+            .map(|p| (p.0, p.1))
+            .collect();
+        Ok(points)
+    }
+
+    /** Transform all the points and split the results in one pass into those deemed
+    inside the destination quad (as per `filter_points_inside`) and those outside,
+    each in their original order. */
+    pub fn transform_partition(
+        &self,
+        points: &[Point2D],
+    ) -> anyhow::Result<(Vec<Point2D>, Vec<Point2D>)> {
+        let transformed = self.transform_many(points)?;
+        Ok(transformed
+            .into_iter()
+            .partition(|point| self.point_is_inside_quad(point)))
+    }
+
+    /** Whether the (destination space) point is inside the destination quad, judged
+    in the quad's own bilinear [0, 1] parameter space and extended on every side by
+    `margin_fraction` of the quad's size. Unlike `ignore_outside_margin`, the margin
+    is therefore independent of the destination quad's scale (e.g. pixels). */
+    pub fn contains_normalized(&self, point: &Point2D, margin_fraction: f32) -> bool {
+        let range = -margin_fraction..=1. + margin_fraction;
+        bilinear_inverse(&self.useable_dst_quad(), *point)
+            .map(|(u, v)| range.contains(&u) && range.contains(&v))
+            .unwrap_or(false)
+    }
+
+    /** Whether every one of the (source space) points transforms to inside the
+    destination quad, as per `filter_points_inside`; stops at the first which does
+    not. A point which fails to transform (e.g. if the transformer is not ready)
+    counts as outside. `true` for no points. */
+    pub fn all_inside(&self, points: &[Point2D]) -> bool {
+        points.iter().all(|point| self.transforms_inside(point))
+    }
+
+    /** Whether any of the (source space) points transforms to inside the
+    destination quad; the counterpart of `all_inside`. `false` for no points. */
+    pub fn any_inside(&self, points: &[Point2D]) -> bool {
+        points.iter().any(|point| self.transforms_inside(point))
+    }
+
+    fn transforms_inside(&self, point: &Point2D) -> bool {
+        self.transform(point)
+            .map(|transformed| self.point_is_inside_quad(&transformed))
+            .unwrap_or(false)
+    }
+
+    /** Same as `filter_points_inside`, but calls `on_reject` for every point which is
+    filtered out, with the point and its signed distance from the destination quad's
+    edges (positive outside; see `signed_distance_to_quad`). */
+    pub fn filter_points_inside_with_callback(
+        &self,
+        points: &[Point2D],
+        mut on_reject: impl FnMut(Point2D, f32),
+    ) -> Vec<Point2D> {
+        let dst_quad = self.useable_dst_quad();
+        points
+            .iter()
+            .filter(|point| {
+                let inside = self.point_is_inside_quad(point);
+                if !inside {
+                    on_reject(**point, signed_distance_to_quad(&dst_quad, point));
+                }
+                inside
+            })
+            .copied()
+            .collect()
+    }
+
+    /** The containment test used by `filter_points_inside`: is the (destination
+    space) point within the destination quad's axis-aligned bounds, extended by
+    `ignore_outside_margin`? The bounds are taken from the min/max of all four
+    corners, so this is exact for rectangles in any corner order; for arbitrary
+    quads, see `point_in_quad`. Always `true` if no margin was set. */
+    fn point_is_inside_quad(&self, point: &Point2D) -> bool {
+        match self.ignore_outside_margin {
+            Some(margin) => {
+                let (x, y) = point;
+                debug!("...Is {x}, {y} outside of {margin}?");
+                // No destination quad set falls back to the "default" [0;1]
+                let (min, max) = bounds(&self.useable_dst_quad());
+                !(*x > (max.0 + margin)
+                    || *x < (min.0 - margin)
+                    || *y > (max.1 + margin)
+                    || *y < (min.1 - margin))
+            }
+            None => true,
+        }
+    }
+
+    /** Classify a (destination space) point by its distance from the edges of the
+    destination quad: inside the quad, points within the edge band (see
+    `QuadTransformerBuilder::with_edge_band`) of an edge are distinguished from those
+    further in; outside, points within `ignore_outside_margin` are distinguished from
+    those beyond it. Without a margin, every outside point is tolerated (as in
+    `filter_points_inside`). Unlike `filter_points_inside`, this uses the quad's
+    actual edges rather than its bounding box. */
+    pub fn classify_point(&self, point: &Point2D) -> PointZone {
+        let signed_distance = signed_distance_to_quad(&self.useable_dst_quad(), point);
+        if signed_distance <= 0. {
+            if -signed_distance < self.edge_band {
+                PointZone::EdgeBand
+            } else {
+                PointZone::Inside
+            }
+        } else {
+            match self.ignore_outside_margin {
+                Some(margin) if signed_distance > margin => PointZone::Rejected,
+                _ => PointZone::OutsideTolerated,
+            }
+        }
+    }
+
+    /** Same as `filter_points_inside`, but returns an error if the transformer is
+    not ready (no source quad has been set yet). */
+    pub fn filter_points_inside_checked(&self, points: &[Point2D]) -> anyhow::Result<Vec<Point2D>> {
+        if !self.is_ready() {
+            return Err(anyhow!("No transform matrix"));
+        }
+        self.filter_points_inside(points)
+    }
+
+    /** Check whether a point in *source* space lies inside the source quad, or
+    within `margin` distance outside of its edges. Always `false` if no source
+    quad has been set. */
+    pub fn src_contains(&self, point: &Point2D, margin: f32) -> bool {
+        match self.src_quad {
+            Some(src_quad) => point_in_quad(&src_quad, point, margin),
+            None => false,
+        }
+    }
+
+    /** The transform matrix as row-major `f64` values, matching the layout OpenCV
+    uses for homographies (`cv::Mat` of `CV_64F`, 3x3). */
+    pub fn matrix_opencv(&self) -> Option<[f64; 9]> {
+        self.transform_matrix.map(|m| {
+            let mut values = [0.; 9];
+            for row in 0..3 {
+                for col in 0..3 {
+                    values[row * 3 + col] = m[(row, col)] as f64;
+                }
+            }
+            values
+        })
+    }
+
+    /** The unit square `(0, 0), (1, 0), (1, 1), (0, 1)` transformed by the matrix:
+    a representative destination quad when only the matrix is known. */
+    pub fn implied_dst_quad(&self) -> Option<RectCorners> {
+        let matrix = self.transform_matrix?;
+        Some(UNIT_SQUARE.map(|(x, y)| {
+            let p = matrix.transform_point(&Point2::new(x, y));
+            (p.x, p.y)
+        }))
+    }
+
+    /** The quad which the matrix maps onto the unit square: a representative source
+    quad when only the matrix is known. `None` if the matrix is not invertible. */
+    pub fn implied_src_quad(&self) -> Option<RectCorners> {
+        let inverse = self.inverse_matrix?;
+        Some(UNIT_SQUARE.map(|(x, y)| {
+            let p = inverse.transform_point(&Point2::new(x, y));
+            (p.x, p.y)
+        }))
+    }
+
+    /** The forward and inverse transform matrices, in that order, each as row-major
+    values (as per `matrix_opencv`), e.g. for shaders which map in both directions.
+    The inverse is cached, so this is cheap to call repeatedly. `None` if the
+    transformer is not ready or the matrix is not invertible. */
+    pub fn matrices_both(&self) -> Option<([f32; 9], [f32; 9])> {
+        Some((
+            row_major(&self.transform_matrix?),
+            row_major(&self.inverse_matrix?),
+        ))
+    }
+
+    /** The transform as a `#[repr(C)]` struct, for passing across a C ABI without
+    exposing nalgebra types. If not ready, the matrix is all zeros. */
+    pub fn to_ffi(&self) -> FfiTransform {
+        FfiTransform {
+            matrix: self.matrix_row_major().unwrap_or([0.; 9]),
+            ready: self.is_ready(),
+        }
+    }
+
+    /** The transform matrix elements row by row, i.e. `[m00, m01, m02, m10, ...]`
+    where `mRC` is the element in row R, column C. This is the order in which
+    `Matrix3::new` takes its arguments. */
+    pub fn matrix_row_major(&self) -> Option<[f32; 9]> {
+        self.transform_matrix.as_ref().map(row_major)
+    }
+
+    /** The transform matrix elements column by column, i.e. `[m00, m10, m20, m01,
+    ...]`; this is how nalgebra stores matrices internally, and the layout expected
+    by e.g. OpenGL's `glUniformMatrix3fv` without transposing. */
+    pub fn matrix_column_major(&self) -> Option<[f32; 9]> {
+        self.transform_matrix.map(|m| {
+            let mut values = [0.; 9];
+            values.copy_from_slice(m.as_slice());
+            values
+        })
+    }
+
+    /** Root-mean-square distance between each transformed `src` point and the
+    corresponding `expected_dst` point, for judging a calibration against held-out
+    correspondences. */
+    pub fn validation_rms(&self, src: &[Point2D], expected_dst: &[Point2D]) -> anyhow::Result<f32> {
+        let errors = self.residuals(src, expected_dst)?;
+        let sum_squared: f32 = errors.iter().map(|e| e * e).sum();
+        Ok((sum_squared / errors.len() as f32).sqrt())
+    }
+
+    /** The minimum, maximum, mean and median distance between each transformed `src`
+    point and the corresponding `expected_dst` point. Unlike `validation_rms`, a
+    large `max` with a small `median` singles out a few bad correspondences. */
+    pub fn error_stats(
+        &self,
+        src: &[Point2D],
+        expected_dst: &[Point2D],
+    ) -> anyhow::Result<ErrorStats> {
+        let mut errors = self.residuals(src, expected_dst)?;
+        errors.sort_by(f32::total_cmp);
+        let n = errors.len();
+        let median = if n % 2 == 0 {
+            (errors[n / 2 - 1] + errors[n / 2]) / 2.
+        } else {
+            errors[n / 2]
+        };
+        Ok(ErrorStats {
+            min: errors[0],
+            max: errors[n - 1],
+            mean: errors.iter().sum::<f32>() / n as f32,
+            median,
+        })
+    }
+
+    /** Distance between each transformed `src` point and its expected destination */
+    fn residuals(&self, src: &[Point2D], expected_dst: &[Point2D]) -> anyhow::Result<Vec<f32>> {
+        if src.len() != expected_dst.len() {
+            return Err(anyhow!(
+                "Got {} source points but {} expected destination points",
+                src.len(),
+                expected_dst.len()
+            ));
+        }
+        if src.is_empty() {
+            return Err(anyhow!("No points to validate against"));
+        }
+        let transformed = src
+            .iter()
+            .map(|point| self.transform(point))
+            .collect::<anyhow::Result<Vec<Point2D>>>()?;
+        Ok(transformed
+            .iter()
+            .zip(expected_dst.iter())
+            .map(|(actual, expected)| distance(actual.0, actual.1, expected.0, expected.1))
+            .collect())
+    }
+
+    /** The magnitude of the projective (bottom row) coefficients of the matrix; zero
+    for a purely affine transform, and larger the stronger the perspective. Note that
+    the coefficients scale with the inverse of the source units, so only compare
+    values for source quads of a similar size. */
+    pub fn perspective_magnitude(&self) -> Option<f32> {
+        self.transform_matrix
+            .map(|m| (m[(2, 0)].powi(2) + m[(2, 1)].powi(2)).sqrt())
+    }
+
+    /** The affine transform which best approximates this one (in the least squares
+    sense) at the four source corners. Applying it needs no perspective divide, so
+    it is cheaper, at the cost of accuracy away from the corners; it is exact if the
+    transform is affine in the first place. Requires a source quad. */
+    pub fn to_affine_approx(&self) -> Option<Matrix3<f32>> {
+        let src_quad = self.src_quad?;
+        let dst = self.transformed_corners().ok()?;
+        fit_affine(&src_quad, &dst)
+    }
+
+    /** A finite point which the transform maps onto itself, if there is one. Fixed
+    points are the eigenvectors of the matrix in homogeneous coordinates, i.e. the
+    solutions of `(M - λI)x = 0` for each real eigenvalue `λ` (just `(M - I)x = 0`
+    when the matrix is normalised so the eigenvalue is 1); those at infinity are
+    skipped. If there are several, e.g. for the identity, any one is returned.
+    Where an eigenvalue's solutions span a line or plane (a whole line of fixed
+    points, or the identity), the basis vector furthest from infinity is used. */
+    pub fn fixed_point(&self) -> Option<Point2D> {
+        let matrix = self.transform_matrix?;
+        let tolerance = 1e-4 * matrix.amax();
+        matrix
+            .complex_eigenvalues()
+            .iter()
+            .filter(|lambda| lambda.im.abs() <= 1e-4 * lambda.re.abs().max(1.))
+            .find_map(|lambda| {
+                let svd = (matrix - Matrix3::identity() * lambda.re).svd(false, true);
+                let v_t = svd.v_t?;
+                let threshold = tolerance.max(svd.singular_values.min());
+                // Rows of v_t are unit vectors, so the largest |z| is the most finite
+                let null = (0..3)
+                    .filter(|&i| svd.singular_values[i] <= threshold)
+                    .map(|i| v_t.row(i).transpose())
+                    .max_by(|a, b| a[2].abs().total_cmp(&b[2].abs()))?;
+                if null[2].abs() <= f32::EPSILON * null.amax() {
+                    None
+                } else {
+                    Some((null[0] / null[2], null[1] / null[2]))
+                }
+            })
+    }
+
+    /** How far the calibration moved between `self` and `other`: the largest
+    destination space distance between where the two transformers map each corner
+    of this transformer's source quad. Requires a source quad. */
+    pub fn max_corner_shift(&self, other: &QuadTransformer) -> anyhow::Result<f32> {
+        let src_quad = self.src_quad.ok_or_else(|| anyhow!("No source quad"))?;
+        src_quad.iter().try_fold(0_f32, |max_shift, corner| {
+            let (x1, y1) = self.transform(corner)?;
+            let (x2, y2) = other.transform(corner)?;
+            Ok(max_shift.max(distance(x1, y1, x2, y2)))
+        })
+    }
+
+    /** The ratio of the largest to the smallest singular value of the upper left
+    2x2 (linear) part of the matrix: 1 for a uniform scale and rotation, and larger
+    the more anisotropic the transform, with a large value warning of poor numerical
+    robustness. Infinite if the linear part is singular. */
+    pub fn affine_condition_number(&self) -> Option<f32> {
+        let m = self.transform_matrix?;
+        let linear = na::Matrix2::new(m[(0, 0)], m[(0, 1)], m[(1, 0)], m[(1, 1)]);
+        let singular_values = linear.singular_values();
+        let (max, min) = (singular_values.max(), singular_values.min());
+        Some(if min > 0. { max / min } else { f32::INFINITY })
+    }
+
+    /** Check that both transformers have the same matrix (elementwise within
+    `epsilon`), and the same destination quad, margin and edge band used for
+    filtering and classifying points. Two transformers which are not yet ready are
+    considered equal. */
+    pub fn approx_eq(&self, other: &QuadTransformer, epsilon: f32) -> bool {
+        let matrices_eq = match (self.transform_matrix, other.transform_matrix) {
+            (Some(a), Some(b)) => (a - b).iter().all(|d| d.abs() <= epsilon),
+            (None, None) => true,
+            _ => false,
+        };
+        let margins_eq = match (self.ignore_outside_margin, other.ignore_outside_margin) {
+            (Some(a), Some(b)) => (a - b).abs() <= epsilon,
+            (None, None) => true,
+            _ => false,
+        };
+        let dst_quads_eq = self
+            .useable_dst_quad()
+            .iter()
+            .zip(other.useable_dst_quad().iter())
+            .all(|(a, b)| (a.0 - b.0).abs() <= epsilon && (a.1 - b.1).abs() <= epsilon);
+        let edge_bands_eq = (self.edge_band - other.edge_band).abs() <= epsilon;
+        matrices_eq && margins_eq && dst_quads_eq && edge_bands_eq
+    }
+
+    /** The transform as a CSS `matrix3d(...)` value, for applying it to a DOM element
+    (with `transform-origin: 0 0`). The homography is embedded in a 4x4 matrix which
+    leaves z untouched, and the 16 values are listed column-major as CSS expects, so
+    the perspective terms come 4th and 8th. */
+    pub fn to_css_matrix3d(&self) -> Option<String> {
+        let m = self.transform_matrix?;
+        let values = [
+            m[(0, 0)],
+            m[(1, 0)],
+            0.,
+            m[(2, 0)],
+            m[(0, 1)],
+            m[(1, 1)],
+            0.,
+            m[(2, 1)],
+            0.,
+            0.,
+            1.,
+            0.,
+            m[(0, 2)],
+            m[(1, 2)],
+            0.,
+            m[(2, 2)],
+        ];
+        Some(format!(
+            "matrix3d({})",
+            values
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))
+    }
+
+    /** A debug visualisation as an SVG document of the given pixel size, showing the
+    source quad (blue), the destination quad (red) and a grid across the source quad
+    mapped through the transform (grey). The view box covers both quads. Requires a
+    source quad to have been set. */
+    pub fn to_svg(&self, width: u32, height: u32) -> Option<String> {
+        const GRID_LINES: usize = 5;
+        const GRID_SAMPLES: usize = 9;
+
+        let src_quad = self.src_quad?;
+        let dst_quad = self.useable_dst_quad();
+
+        let format_points = |points: &[Point2D]| {
+            points
+                .iter()
+                .map(|(x, y)| format!("{x},{y}"))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+
+        let (src_min, src_max) = bounds(&src_quad);
+        let (dst_min, dst_max) = bounds(&dst_quad);
+        let (min_x, min_y) = (src_min.0.min(dst_min.0), src_min.1.min(dst_min.1));
+        let (max_x, max_y) = (src_max.0.max(dst_max.0), src_max.1.max(dst_max.1));
+        let padding = 0.05 * (max_x - min_x).max(max_y - min_y);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"{} {} {} {}\">\n",
+            min_x - padding,
+            min_y - padding,
+            max_x - min_x + 2. * padding,
+            max_y - min_y + 2. * padding
+        );
+        let style = "fill=\"none\" vector-effect=\"non-scaling-stroke\"";
+        svg += &format!(
+            "  <polygon class=\"src-quad\" points=\"{}\" stroke=\"blue\" {style}/>\n",
+            format_points(&src_quad)
+        );
+        svg += &format!(
+            "  <polygon class=\"dst-quad\" points=\"{}\" stroke=\"red\" {style}/>\n",
+            format_points(&dst_quad)
+        );
+        for i in 0..GRID_LINES {
+            let t = i as f32 / (GRID_LINES - 1) as f32;
+            for along_u in [true, false] {
+                let line: Vec<Point2D> = (0..GRID_SAMPLES)
+                    .map(|j| {
+                        let s = j as f32 / (GRID_SAMPLES - 1) as f32;
+                        let uv = if along_u { (s, t) } else { (t, s) };
+                        self.transform_normalized(uv)
+                    })
+                    .collect::<anyhow::Result<_>>()
+                    .ok()?;
+                svg += &format!(
+                    "  <polyline class=\"grid\" points=\"{}\" stroke=\"grey\" {style}/>\n",
+                    format_points(&line)
+                );
+            }
+        }
+        svg += "</svg>\n";
+        Some(svg)
+    }
+
+    /** An approximation of the largest axis-aligned rectangle inside the destination
+    quad, as its (min, max) corners, e.g. for placing UI within a skewed surface.
+    This is the destination quad's bounding box, shrunk about the quad's centroid
+    (by bisection) until all four corners are inside the quad, so it keeps the
+    bounding box's aspect ratio and is not necessarily the largest possible. `None`
+    if the centroid is not itself inside the quad. */
+    pub fn inscribed_rect(&self) -> Option<(Point2D, Point2D)> {
+        const ITERATIONS: usize = 32;
+
+        let dst_quad = self.useable_dst_quad();
+        let (cx, cy) = quad_centroid(&dst_quad);
+        if !point_in_quad(&dst_quad, &(cx, cy), 0.) {
+            return None;
+        }
+        let (min, max) = bounds(&dst_quad);
+        let rect_at = |t: f32| {
+            (
+                (cx + t * (min.0 - cx), cy + t * (min.1 - cy)),
+                (cx + t * (max.0 - cx), cy + t * (max.1 - cy)),
+            )
+        };
+        let fits = |t: f32| {
+            let (min, max) = rect_at(t);
+            [min, (max.0, min.1), max, (min.0, max.1)]
+                .iter()
+                .all(|corner| point_in_quad(&dst_quad, corner, 0.))
+        };
+
+        let (mut inside, mut outside) = (0., 1.);
+        if fits(1.) {
+            inside = 1.;
+        } else {
+            for _ in 0..ITERATIONS {
+                let t = (inside + outside) / 2.;
+                if fits(t) {
+                    inside = t;
+                } else {
+                    outside = t;
+                }
+            }
+        }
+        Some(rect_at(inside))
+    }
+
+    /** The midpoints of the destination quad's edges, in the order given by
+    `quad_edge_midpoints`, e.g. for drawing edge handles. `None` if the transformer
+    is not ready. */
+    pub fn dst_edge_midpoints(&self) -> Option<[Point2D; 4]> {
+        self.is_ready()
+            .then(|| quad_edge_midpoints(&self.useable_dst_quad()))
+    }
+
+    /** The destination quad split into two triangles along its 'left top' to
+    'right bottom' diagonal, `[a, b, c]` and `[a, c, d]`, both with the same winding
+    as the quad itself. `None` if the transformer is not ready. */
+    pub fn dst_triangles(&self) -> Option<[[Point2D; 3]; 2]> {
+        if !self.is_ready() {
+            return None;
+        }
+        let [a, b, c, d] = self.useable_dst_quad();
+        Some([[a, b, c], [a, c, d]])
+    }
+
+    /** The inverse of `approx_eq`: whether the transformers differ by more than
+    `epsilon`, e.g. to decide whether a changed matrix needs uploading again. */
+    pub fn differs_from(&self, other: &QuadTransformer, epsilon: f32) -> bool {
+        !self.approx_eq(other, epsilon)
+    }
+
+    /** Serialize the transformer into a compact, versioned binary format which can
+    be read back with `from_bytes`. Absent values (e.g. no destination quad) are
+    recorded in a flags byte and written as zeros. */
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut flags = 0;
+        let mut values: Vec<f32> = Vec::with_capacity(27);
+
+        let matrix = self.transform_matrix.unwrap_or_else(Matrix3::zeros);
+        if self.transform_matrix.is_some() {
+            flags |= FLAG_MATRIX;
+        }
+        // Write row-major, independent of nalgebra's internal layout
+        values.extend(matrix.transpose().iter());
+
+        for (quad, flag) in [
+            (self.src_quad, FLAG_SRC_QUAD),
+            (self.dst_quad, FLAG_DST_QUAD),
+        ] {
+            if quad.is_some() {
+                flags |= flag;
+            }
+            values.extend(quad.unwrap_or_default().iter().flat_map(|(x, y)| [*x, *y]));
+        }
+
+        if self.ignore_outside_margin.is_some() {
+            flags |= FLAG_MARGIN;
+        }
+        values.push(self.ignore_outside_margin.unwrap_or_default());
+        values.push(self.edge_band);
+
+        if self.fallback_identity {
+            flags |= FLAG_FALLBACK_IDENTITY;
+        }
+        if self.coordinate_system == CoordinateSystem::MathYUp {
+            flags |= FLAG_MATH_Y_UP;
+        }
+        if self.transform_mode == TransformMode::FrontOnly {
+            flags |= FLAG_FRONT_ONLY;
+        }
+        if self.centered_destination {
+            flags |= FLAG_CENTERED_DESTINATION;
+        }
+
+        let mut options = 0;
+        if self.aspect_mode == AspectMode::Fill {
+            options |= OPTION_ASPECT_FILL;
+        }
+        if self.not_ready_policy == NotReadyPolicy::Passthrough {
+            options |= OPTION_NOT_READY_PASSTHROUGH;
+        }
+        options |= match self.nan_policy {
+            NanPolicy::Skip => 0,
+            NanPolicy::Fail => OPTION_NAN_FAIL,
+            NanPolicy::PassThrough => OPTION_NAN_PASS_THROUGH,
+        };
+
+        let mut bytes = Vec::with_capacity(BYTES_LEN);
+        bytes.extend_from_slice(BYTES_MAGIC);
+        bytes.push(BYTES_VERSION);
+        bytes.push(flags);
+        bytes.push(options);
+        for value in values {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /** Read back a transformer written by `to_bytes` */
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<QuadTransformer> {
+        if bytes.len() < 5 || &bytes[0..3] != BYTES_MAGIC {
+            return Err(anyhow!("Not a serialized QuadTransformer"));
+        }
+        let (expected_len, header_len) = match bytes[3] {
+            1 => (BYTES_LEN_V1, 5),
+            BYTES_VERSION => (BYTES_LEN, 6),
+            version => return Err(anyhow!("Unsupported QuadTransformer version {version}")),
+        };
+        if bytes.len() != expected_len {
+            return Err(anyhow!(
+                "Expected {expected_len} bytes for a serialized QuadTransformer, got {}",
+                bytes.len()
+            ));
+        }
+        let flags = bytes[4];
+        let options = if header_len > 5 { bytes[5] } else { 0 };
+        let values: Vec<f32> = bytes[header_len..]
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        let quad = |offset: usize| -> RectCorners {
+            let v = &values[offset..offset + 8];
+            [(v[0], v[1]), (v[2], v[3]), (v[4], v[5]), (v[6], v[7])]
+        };
+        let has = |flag: u8| flags & flag != 0;
+
+        let dst_quad = has(FLAG_DST_QUAD).then(|| quad(17));
+        let margin = has(FLAG_MARGIN).then_some(values[25]);
+        let mut transformer = QuadTransformer::new(None, dst_quad, margin);
+        transformer
+            .set_transform_matrix(has(FLAG_MATRIX).then(|| Matrix3::from_row_slice(&values[0..9])));
+        transformer.src_quad = has(FLAG_SRC_QUAD).then(|| quad(9));
+        transformer.fallback_identity = has(FLAG_FALLBACK_IDENTITY);
+        transformer.edge_band = values.get(26).copied().unwrap_or_default();
+        if has(FLAG_MATH_Y_UP) {
+            transformer.coordinate_system = CoordinateSystem::MathYUp;
+        }
+        if has(FLAG_FRONT_ONLY) {
+            transformer.transform_mode = TransformMode::FrontOnly;
+        }
+        transformer.centered_destination = has(FLAG_CENTERED_DESTINATION);
+        if options & OPTION_ASPECT_FILL != 0 {
+            transformer.aspect_mode = AspectMode::Fill;
+        }
+        if options & OPTION_NOT_READY_PASSTHROUGH != 0 {
+            transformer.not_ready_policy = NotReadyPolicy::Passthrough;
+        }
+        if options & OPTION_NAN_FAIL != 0 {
+            transformer.nan_policy = NanPolicy::Fail;
+        } else if options & OPTION_NAN_PASS_THROUGH != 0 {
+            transformer.nan_policy = NanPolicy::PassThrough;
+        }
+        Ok(transformer)
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.transform_matrix.is_some()
+    }
+}
+
+/** Composition following the matrix convention: `&a * &b` applies `b` first and
+then `a`, i.e. it is `b.then(&a)`. */
+impl std::ops::Mul for &QuadTransformer {
+    type Output = QuadTransformer;
+
+    fn mul(self, rhs: &QuadTransformer) -> QuadTransformer {
+        rhs.then(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuadRole {
+    Source,
+    Destination,
+}
+
+/** A problem with the quads given to a `QuadTransformerBuilder`; see
+`QuadTransformerBuilder::validate` */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /** The quad was given as a list of points, but not exactly four of them */
+    WrongCornerCount {
+        role: QuadRole,
+        count: usize,
+    },
+    /** Corners coincide, or the quad has (almost) no area */
+    Degenerate {
+        role: QuadRole,
+    },
+    /** Opposite edges cross (a "bowtie"), usually because two corners were given in
+    the wrong order */
+    SelfIntersecting {
+        role: QuadRole,
+    },
+    NonConvex {
+        role: QuadRole,
+    },
+    /** The source and destination corners wind in opposite directions (after
+    allowing for the y flip of `CoordinateSystem::MathYUp`), so the transform
+    would mirror points */
+    Reflected,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::WrongCornerCount { role, count } => {
+                write!(f, "{role:?} quad has {count} corners instead of 4")
+            }
+            ValidationIssue::Degenerate { role } => write!(f, "{role:?} quad is degenerate"),
+            ValidationIssue::SelfIntersecting { role } => write!(
+                f,
+                "{role:?} quad is self-intersecting; check the corner order"
+            ),
+            ValidationIssue::NonConvex { role } => write!(f, "{role:?} quad is not convex"),
+            ValidationIssue::Reflected => write!(
+                f,
+                "Source and destination quads have opposite corner winding"
+            ),
+        }
+    }
+}
+
+/** Configures and builds a `QuadTransformer`; see `QuadTransformer::builder` */
+#[derive(Clone, Default)]
+pub struct QuadTransformerBuilder {
+    src_quad: Option<RectCorners>,
+    dst_quad: Option<RectCorners>,
+    ignore_outside_margin: Option<f32>,
+    fallback_identity: bool,
+    coordinate_system: CoordinateSystem,
+    transform_mode: TransformMode,
+    edge_band: f32,
+    centered_destination: bool,
+    nan_policy: NanPolicy,
+    aspect_mode: AspectMode,
+    not_ready_policy: NotReadyPolicy,
+    wrong_corner_counts: Vec<(QuadRole, usize)>,
+}
+
+impl QuadTransformerBuilder {
+    pub fn with_src_quad(mut self, src_quad: RectCorners) -> Self {
+        self.src_quad = Some(src_quad);
+        self
+    }
+
+    /** Set the source quad from a dynamically-sized list of points; anything other
+    than four points is reported by `validate` and fails `build`. */
+    pub fn with_src_points(mut self, points: &[Point2D]) -> Self {
+        match corners_from_slice(points) {
+            Ok(quad) => self.src_quad = Some(quad),
+            Err(_) => self
+                .wrong_corner_counts
+                .push((QuadRole::Source, points.len())),
+        }
+        self
+    }
+
+    pub fn with_dst_quad(mut self, dst_quad: RectCorners) -> Self {
+        self.dst_quad = Some(dst_quad);
+        self
+    }
+
+    /** As per `with_src_points`, for the destination quad */
+    pub fn with_dst_points(mut self, points: &[Point2D]) -> Self {
+        match corners_from_slice(points) {
+            Ok(quad) => self.dst_quad = Some(quad),
+            Err(_) => self
+                .wrong_corner_counts
+                .push((QuadRole::Destination, points.len())),
+        }
+        self
+    }
+
+    pub fn with_ignore_outside_margin(mut self, margin: f32) -> Self {
+        self.ignore_outside_margin = Some(margin);
+        self
+    }
+
+    /** If enabled, a quad which cannot be solved (e.g. degenerate corners) results in
+    an identity (pass-through) transform and a logged warning, rather than an error.
+    This also applies to later calls to `QuadTransformer::set_new_quad`. */
+    pub fn with_fallback_identity(mut self, enabled: bool) -> Self {
+        self.fallback_identity = enabled;
+        self
+    }
+
+    /** The coordinate system used to generate the default destination quad, if no
+    destination quad is given. Defaults to `CoordinateSystem::ScreenYDown`. */
+    pub fn with_coordinate_system(mut self, coordinate_system: CoordinateSystem) -> Self {
+        self.coordinate_system = coordinate_system;
+        self
+    }
+
+    /** How points behind the projection plane are handled; defaults to
+    `TransformMode::Standard`. */
+    pub fn with_transform_mode(mut self, transform_mode: TransformMode) -> Self {
+        self.transform_mode = transform_mode;
+        self
+    }
+
+    /** How batch methods treat input points with NaN or infinite coordinates;
+    defaults to `NanPolicy::Skip`. */
+    pub fn with_nan_policy(mut self, nan_policy: NanPolicy) -> Self {
+        self.nan_policy = nan_policy;
+        self
+    }
+
+    /** How differing source and destination aspect ratios are handled; defaults to
+    `AspectMode::Stretch`. */
+    pub fn with_aspect_mode(mut self, aspect_mode: AspectMode) -> Self {
+        self.aspect_mode = aspect_mode;
+        self
+    }
+
+    /** What `transform` does before a source quad has been set; defaults to
+    `NotReadyPolicy::Error`. */
+    pub fn with_not_ready_policy(mut self, not_ready_policy: NotReadyPolicy) -> Self {
+        self.not_ready_policy = not_ready_policy;
+        self
+    }
+
+    /** If enabled, the default destination quad (used if none is given) is the 1x1
+    box centred on the origin, i.e. spanning [-0.5, 0.5], rather than [0, 1]. */
+    pub fn with_centered_destination(mut self, enabled: bool) -> Self {
+        self.centered_destination = enabled;
+        self
+    }
+
+    /** Width of the band just inside the destination quad's edges which
+    `QuadTransformer::classify_point` reports as `PointZone::EdgeBand`. Defaults
+    to zero, i.e. no band. */
+    pub fn with_edge_band(mut self, edge_band: f32) -> Self {
+        self.edge_band = edge_band;
+        self
+    }
+
+    /** Check the quads without building anything, returning every issue found
+    (rather than stopping at the first). An empty list means the quads look sound. */
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues: Vec<ValidationIssue> = self
+            .wrong_corner_counts
+            .iter()
+            .map(|(role, count)| ValidationIssue::WrongCornerCount {
+                role: *role,
+                count: *count,
+            })
+            .collect();
+
+        let dst_quad = self
+            .dst_quad
+            .unwrap_or_else(|| default_dst_quad(self.coordinate_system, self.centered_destination));
+        let quads = [
+            (QuadRole::Source, self.src_quad),
+            (QuadRole::Destination, Some(dst_quad)),
+        ];
+        for (role, quad) in quads {
+            if let Some(quad) = quad {
+                // Checked first, as a symmetric bowtie also has no area
+                if is_self_intersecting(&quad) {
+                    issues.push(ValidationIssue::SelfIntersecting { role });
+                } else if is_degenerate(&quad) {
+                    issues.push(ValidationIssue::Degenerate { role });
+                } else if !is_convex(&quad) {
+                    issues.push(ValidationIssue::NonConvex { role });
+                }
+            }
+        }
+
+        // Compare against the winding of the coordinate system's own destination quad,
+        // as MathYUp is deliberately flipped relative to a (y down) source quad
+        let expected_winding = signed_area(&self.coordinate_system.default_dst_quad()).signum();
+        if let Some(src_quad) = self.src_quad {
+            let (src_area, dst_area) = (signed_area(&src_quad), signed_area(&dst_quad));
+            if src_area * dst_area * expected_winding < 0. {
+                issues.push(ValidationIssue::Reflected);
+            }
+        }
+
+        issues
+    }
+
+    pub fn build(self) -> anyhow::Result<QuadTransformer> {
+        if let Some((role, count)) = self.wrong_corner_counts.first() {
+            return Err(anyhow!(
+                "{}",
+                ValidationIssue::WrongCornerCount {
+                    role: *role,
+                    count: *count
+                }
+            ));
+        }
+        let mut transformer = QuadTransformer::new(None, self.dst_quad, self.ignore_outside_margin);
+        transformer.fallback_identity = self.fallback_identity;
+        transformer.coordinate_system = self.coordinate_system;
+        transformer.transform_mode = self.transform_mode;
+        transformer.edge_band = self.edge_band;
+        transformer.centered_destination = self.centered_destination;
+        transformer.nan_policy = self.nan_policy;
+        transformer.aspect_mode = self.aspect_mode;
+        transformer.not_ready_policy = self.not_ready_policy;
+        if let Some(src_quad) = self.src_quad {
+            let useable_dst_quad = transformer.useable_dst_quad();
+            let matrix = transformer.solve(&src_quad, &useable_dst_quad)?;
+            transformer.set_transform_matrix(Some(matrix));
+            transformer.src_quad = Some(src_quad);
+        }
+        Ok(transformer)
+    }
+}
+
+/** Stateful containment filter for tracking, with hysteresis to prevent points
+flickering in and out at the edge of the destination quad: a point must come at
+least `inner_margin` inside the quad's edges to become "inside", but then stays
+inside until it goes further than `outer_margin` outside of them.
+
+Membership is remembered by index, so points should be given in a consistent
+order from one `update` to the next. */
+pub struct HysteresisFilter {
+    transformer: QuadTransformer,
+    inner_margin: f32,
+    outer_margin: f32,
+    inside: Vec<bool>,
+}
+
+impl HysteresisFilter {
+    pub fn new(transformer: QuadTransformer, inner_margin: f32, outer_margin: f32) -> Self {
+        HysteresisFilter {
+            transformer,
+            inner_margin,
+            outer_margin,
+            inside: Vec::new(),
+        }
+    }
+
+    /** Transform the (source space) points, and return whether each is now
+    considered inside the destination quad. */
+    pub fn update(&mut self, points: &[Point2D]) -> anyhow::Result<Vec<bool>> {
+        // Not transform_many, which could skip points and so shift the indices
+        let transformed = points
+            .iter()
+            .map(|point| self.transformer.transform(point))
+            .collect::<anyhow::Result<Vec<Point2D>>>()?;
+        let dst_quad = self.transformer.useable_dst_quad();
+        self.inside.resize(points.len(), false);
+        for (inside, point) in self.inside.iter_mut().zip(transformed.iter()) {
+            let signed_distance = signed_distance_to_quad(&dst_quad, point);
+            *inside = if *inside {
+                signed_distance <= self.outer_margin
+            } else {
+                signed_distance <= -self.inner_margin
+            };
+        }
+        Ok(self.inside.clone())
+    }
+
+    pub fn transformer(&self) -> &QuadTransformer {
+        &self.transformer
+    }
+}
+
+/** Wraps a `QuadTransformer` for live calibration, smoothing jittery source quad
+detections with an exponential moving average of each corner before rebuilding. */
+pub struct SmoothedQuadTransformer {
+    transformer: QuadTransformer,
+}
+
+impl SmoothedQuadTransformer {
+    /** Smoothing starts from the transformer's source quad, if it has one */
+    pub fn new(transformer: QuadTransformer) -> Self {
+        SmoothedQuadTransformer { transformer }
+    }
+
+    /** Move each smoothed corner `alpha` of the way (0 to 1) towards the newly
+    detected quad, and rebuild the transform. The first quad is used as-is. */
+    pub fn update_src_quad(&mut self, quad: RectCorners, alpha: f32) -> anyhow::Result<()> {
+        if !(0. ..=1.).contains(&alpha) {
+            return Err(anyhow!(
+                "Smoothing alpha must be between 0 and 1, got {alpha}"
+            ));
+        }
+        let smoothed = match self.transformer.src_quad {
+            Some(previous) => {
+                let mut smoothed = previous;
+                for (corner, target) in smoothed.iter_mut().zip(quad.iter()) {
+                    corner.0 += alpha * (target.0 - corner.0);
+                    corner.1 += alpha * (target.1 - corner.1);
+                }
+                smoothed
+            }
+            None => quad,
+        };
+        self.transformer.try_set_src_quad(smoothed)
+    }
+
+    pub fn smoothed_src_quad(&self) -> Option<RectCorners> {
+        self.transformer.src_quad
+    }
+
+    pub fn transformer(&self) -> &QuadTransformer {
+        &self.transformer
+    }
+}
+
+/** Wraps a `QuadTransformer` during setup, adjusting its source quad as the
+calibration is refined. */
+pub struct QuadCalibrator {
+    transformer: QuadTransformer,
+}
+
+impl QuadCalibrator {
+    pub fn new(transformer: QuadTransformer) -> Self {
+        QuadCalibrator { transformer }
+    }
+
+    /** Grow the source quad minimally so that it contains the (source space) point,
+    and rebuild the transform. If the nearest part of the quad's boundary is an edge,
+    that edge is moved outwards, parallel to itself, until the point lies on it;
+    if it is a corner, the corner is moved onto the point. Points already inside are
+    ignored, and the transformer is left unchanged if the rebuild fails. Requires a
+    source quad. */
+    pub fn expand_to_include(&mut self, point: Point2D) -> anyhow::Result<()> {
+        let mut src_quad = self
+            .transformer
+            .src_quad
+            .ok_or_else(|| anyhow!("No source quad to expand"))?;
+        if point_in_quad(&src_quad, &point, 0.) {
+            return Ok(());
+        }
+
+        let (edge, nearest) = (0..4)
+            .map(|i| {
+                (
+                    i,
+                    closest_point_on_segment(&point, &src_quad[i], &src_quad[(i + 1) % 4]),
+                )
+            })
+            .min_by(|(_, a), (_, b)| {
+                distance(point.0, point.1, a.0, a.1)
+                    .total_cmp(&distance(point.0, point.1, b.0, b.1))
+            })
+            .expect("a quad has edges");
+        let (start, end) = (edge, (edge + 1) % 4);
+        if nearest == src_quad[start] {
+            src_quad[start] = point;
+        } else if nearest == src_quad[end] {
+            src_quad[end] = point;
+        } else {
+            let (dx, dy) = (point.0 - nearest.0, point.1 - nearest.1);
+            for i in [start, end] {
+                src_quad[i].0 += dx;
+                src_quad[i].1 += dy;
+            }
+        }
+        self.transformer.try_set_src_quad(src_quad)
+    }
+
+    pub fn transformer(&self) -> &QuadTransformer {
+        &self.transformer
+    }
+}
+
+/** Wraps a `QuadTransformer` to reduce output jitter for tracked points: each
+transformed position is blended with the previous output for the same ID, an
+exponential moving average with weight `alpha` for the new position. */
+pub struct SmoothedOutput {
+    transformer: QuadTransformer,
+    alpha: f32,
+    last_positions: std::collections::HashMap<u64, Point2D>,
+}
+
+impl SmoothedOutput {
+    pub fn new(transformer: QuadTransformer, alpha: f32) -> anyhow::Result<Self> {
+        if !(0. ..=1.).contains(&alpha) {
+            return Err(anyhow!(
+                "Smoothing alpha must be between 0 and 1, got {alpha}"
+            ));
+        }
+        Ok(SmoothedOutput {
+            transformer,
+            alpha,
+            last_positions: std::collections::HashMap::new(),
+        })
+    }
+
+    /** Transform the point and move the smoothed output for `id` `alpha` of the way
+    towards it. The first point for an ID is returned as-is. */
+    pub fn transform_smoothed(&mut self, id: u64, point: Point2D) -> anyhow::Result<Point2D> {
+        let (x, y) = self.transformer.transform(&point)?;
+        let smoothed = match self.last_positions.get(&id) {
+            Some((last_x, last_y)) => (
+                last_x + self.alpha * (x - last_x),
+                last_y + self.alpha * (y - last_y),
+            ),
+            None => (x, y),
+        };
+        self.last_positions.insert(id, smoothed);
+        Ok(smoothed)
+    }
+
+    /** Stop tracking the ID, e.g. once it is lost, so a later point with the same ID
+    starts afresh */
+    pub fn forget(&mut self, id: u64) {
+        self.last_positions.remove(&id);
+    }
+
+    pub fn transformer(&self) -> &QuadTransformer {
+        &self.transformer
+    }
+}
+
+/** Quantized (source quad, destination quad, margin) used as a cache key */
+type CacheKey = ([i64; 8], Option<[i64; 8]>, Option<i64>);
+
+/** A small cache of built transformers for source quads which recur, keyed by the
+quads (and margin) rounded to a multiple of `quantum`, so that tiny floating point
+differences still hit the cache. When full, the least recently used entry is
+evicted. */
+pub struct QuadTransformCache {
+    capacity: usize,
+    quantum: f32,
+    /** Least recently used first */
+    entries: Vec<(CacheKey, QuadTransformer)>,
+}
+
+impl QuadTransformCache {
+    /** A cache holding up to `capacity` transformers (at least one). The `quantum`
+    must be finite and positive. */
+    pub fn new(capacity: usize, quantum: f32) -> anyhow::Result<Self> {
+        if !quantum.is_finite() || quantum <= 0. {
+            return Err(anyhow!(
+                "Cache quantum must be finite and positive, got {quantum}"
+            ));
+        }
+        Ok(QuadTransformCache {
+            capacity: capacity.max(1),
+            quantum,
+            entries: Vec::new(),
+        })
+    }
+
+    /** The cached transformer for these quads and margin, building (and caching) one
+    if there is none. A cached transformer keeps the exact quads it was first built
+    with. */
+    pub fn get_or_build(
+        &mut self,
+        src_quad: RectCorners,
+        dst_quad: Option<RectCorners>,
+        ignore_outside_margin: Option<f32>,
+    ) -> anyhow::Result<&QuadTransformer> {
+        let key = self.key(&src_quad, dst_quad.as_ref(), ignore_outside_margin);
+        let entry = match self.entries.iter().position(|(k, _)| *k == key) {
+            Some(index) => self.entries.remove(index),
+            None => {
+                let transformer =
+                    QuadTransformer::try_from_quads(src_quad, dst_quad, ignore_outside_margin)?;
+                if self.entries.len() >= self.capacity {
+                    self.entries.remove(0);
+                }
+                (key, transformer)
+            }
+        };
+        self.entries.push(entry);
+        Ok(&self.entries[self.entries.len() - 1].1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn key(
+        &self,
+        src_quad: &RectCorners,
+        dst_quad: Option<&RectCorners>,
+        margin: Option<f32>,
+    ) -> CacheKey {
+        let quantize = |value: f32| (value / self.quantum).round() as i64;
+        let quantize_quad = |quad: &RectCorners| {
+            let mut values = [0; 8];
+            for (i, (x, y)) in quad.iter().enumerate() {
+                values[i * 2] = quantize(*x);
+                values[i * 2 + 1] = quantize(*y);
+            }
+            values
+        };
+        (
+            quantize_quad(src_quad),
+            dst_quad.map(quantize_quad),
+            margin.map(quantize),
+        )
+    }
+}
+
+fn row_major(m: &Matrix3<f32>) -> [f32; 9] {
+    let mut values = [0.; 9];
+    for row in 0..3 {
+        for col in 0..3 {
+            values[row * 3 + col] = m[(row, col)];
+        }
+    }
+    values
+}
+
+/** Width over height of the quad, from the average lengths of opposite edges */
+fn quad_aspect(quad: &RectCorners) -> f32 {
+    let edge = |i: usize| {
+        let (a, b) = (quad[i], quad[(i + 1) % 4]);
+        distance(a.0, a.1, b.0, b.1)
+    };
+    (edge(0) + edge(2)) / (edge(1) + edge(3))
+}
+
+/** The centred region of `src_quad` (in its bilinear parameter space) with the same
+aspect ratio as `dst_quad`, cropping whichever sides of the source overflow */
+fn aspect_fill_region(src_quad: &RectCorners, dst_quad: &RectCorners) -> RectCorners {
+    let ratio = quad_aspect(dst_quad) / quad_aspect(src_quad);
+    if !(ratio.is_finite() && ratio > 0.) {
+        return *src_quad;
+    }
+    let (inset_u, inset_v) = if ratio < 1. {
+        // Source is relatively wider: crop the left and right
+        ((1. - ratio) / 2., 0.)
+    } else {
+        // Source is relatively taller: crop the top and bottom
+        (0., (1. - 1. / ratio) / 2.)
+    };
+    [
+        (inset_u, inset_v),
+        (1. - inset_u, inset_v),
+        (1. - inset_u, 1. - inset_v),
+        (inset_u, 1. - inset_v),
+    ]
+    .map(|uv| bilinear_point(src_quad, uv))
+}
+
+/** The grids of `sample_grid` and `warp_field` include both edges, so need at least
+two samples each way */
+fn check_grid_size(cols: usize, rows: usize) -> anyhow::Result<()> {
+    if cols < 2 || rows < 2 {
+        return Err(anyhow!(
+            "Grid needs at least 2x2 samples, got {cols}x{rows}"
+        ));
+    }
+    Ok(())
+}
+
+/** The 1x1 destination quad used when none is given */
+fn default_dst_quad(coordinate_system: CoordinateSystem, centered: bool) -> RectCorners {
+    let quad = coordinate_system.default_dst_quad();
+    if centered {
+        let offset = DST_SIZE / 2.;
+        quad.map(|(x, y)| (x - offset, y - offset))
+    } else {
+        quad
+    }
+}
+
+/** Convert a dynamically-sized list of points (e.g. parsed from JSON) into quad
+corners, failing with a descriptive error unless there are exactly four. */
+pub fn corners_from_slice(points: &[Point2D]) -> anyhow::Result<RectCorners> {
+    match points {
+        [a, b, c, d] => Ok([*a, *b, *c, *d]),
+        _ => Err(anyhow!(
+            "A quad needs exactly 4 corners, but {} were supplied",
+            points.len()
+        )),
+    }
+}
+
+/** A source quad for a first-pass calibration: the axis-aligned bounding box of
+the points, grown by `padding` on every side, in the usual corner order. */
+pub fn src_quad_from_points(points: &[Point2D], padding: f32) -> anyhow::Result<RectCorners> {
+    if points.is_empty() {
+        return Err(anyhow!("Cannot build a quad from no points"));
+    }
+    let (min, max) = bounds(points);
+    let (min, max) = (
+        (min.0 - padding, min.1 - padding),
+        (max.0 + padding, max.1 + padding),
+    );
+    Ok([min, (max.0, min.1), max, (min.0, max.1)])
+}
+
+/** Corners of an axis-aligned `aspect * height` by `height` rectangle with its left
+top corner at the origin, in the usual corner order, e.g. a destination quad for a
+display of known aspect ratio (width / height). Both values must be finite and
+positive. */
+pub fn dst_quad_from_aspect(aspect: f32, height: f32) -> anyhow::Result<RectCorners> {
+    let width = aspect * height;
+    for (name, value) in [("aspect", aspect), ("height", height), ("width", width)] {
+        if !value.is_finite() || value <= 0. {
+            return Err(anyhow!(
+                "Destination {name} must be finite and positive, got {value}"
+            ));
+        }
+    }
+    Ok([(0., 0.), (width, 0.), (width, height), (0., height)])
+}
+
+/** Corners of a `size` (width, height) rectangle centred on `center` and rotated by
+`angle_rad` about it, in the usual clockwise 'left top', 'right top', 'right bottom',
+'left bottom' order (as they would be before rotation). Suitable for use as a
+destination quad. */
+pub fn rotated_rect(center: Point2D, size: (f32, f32), angle_rad: f32) -> RectCorners {
+    let (half_width, half_height) = (size.0 / 2., size.1 / 2.);
+    let (sin, cos) = angle_rad.sin_cos();
+    let rotate = |(x, y): Point2D| (center.0 + x * cos - y * sin, center.1 + x * sin + y * cos);
+    [
+        rotate((-half_width, -half_height)),
+        rotate((half_width, -half_height)),
+        rotate((half_width, half_height)),
+        rotate((-half_width, half_height)),
+    ]
+}
+
+/** The average of the quad's four corners */
+fn quad_centroid(quad: &RectCorners) -> Point2D {
+    let (sum_x, sum_y) = quad
+        .iter()
+        .fold((0., 0.), |(sum_x, sum_y), (x, y)| (sum_x + x, sum_y + y));
+    (sum_x / 4., sum_y / 4.)
+}
+
+/** The (min, max) corners of the points' axis-aligned bounding box */
+fn bounds(points: &[Point2D]) -> (Point2D, Point2D) {
+    points.iter().fold(
+        (
+            (f32::INFINITY, f32::INFINITY),
+            (f32::NEG_INFINITY, f32::NEG_INFINITY),
+        ),
+        |(min, max), (x, y)| {
+            (
+                (min.0.min(*x), min.1.min(*y)),
+                (max.0.max(*x), max.1.max(*y)),
+            )
+        },
+    )
+}
+
+/** Polygon containment test for an arbitrary (not necessarily convex) quad: the
+point is inside if it is within the quad's edges (even-odd rule), or no further
+than `margin` distance from the nearest edge. */
+pub fn point_in_quad(quad: &RectCorners, point: &Point2D, margin: f32) -> bool {
+    let (x, y) = *point;
+    let mut inside = false;
+    for i in 0..4 {
+        let (xi, yi) = quad[i];
+        let (xj, yj) = quad[(i + 3) % 4];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+    }
+    inside || distance_to_quad_edges(quad, point) <= margin
+}
+
+/** Area of the intersection of two convex quads (e.g. two projectors' destination
+quads, for edge blending); zero if they do not overlap */
+pub fn quad_overlap_area(a: &RectCorners, b: &RectCorners) -> f32 {
+    signed_area(&clip_polygon(a, b)).abs() / 2.
+}
+
+/** The midpoints of the quad's four edges: top (left top to right top), right,
+bottom and left, following the corner order */
+pub fn quad_edge_midpoints(quad: &RectCorners) -> [Point2D; 4] {
+    [0, 1, 2, 3].map(|i| {
+        let (a, b) = (quad[i], quad[(i + 1) % 4]);
+        ((a.0 + b.0) / 2., (a.1 + b.1) / 2.)
+    })
+}
+
+/** Whether either pair of opposite edges of the quad cross each other, making a
+"bowtie" shape (as opposed to a quad which is merely concave) */
+pub fn is_self_intersecting(quad: &RectCorners) -> bool {
+    let orientation =
+        |a: Point2D, b: Point2D, c: Point2D| cross((b.0 - a.0, b.1 - a.1), (c.0 - a.0, c.1 - a.1));
+    let segments_cross = |a: Point2D, b: Point2D, c: Point2D, d: Point2D| {
+        orientation(a, b, c) * orientation(a, b, d) < 0.
+            && orientation(c, d, a) * orientation(c, d, b) < 0.
+    };
+    let [a, b, c, d] = *quad;
+    segments_cross(a, b, c, d) || segments_cross(b, c, d, a)
+}
+
+/** Distance from the point to the nearest edge of the quad; negative if the point
+is inside the quad, positive if outside */
+pub fn signed_distance_to_quad(quad: &RectCorners, point: &Point2D) -> f32 {
+    let distance = distance_to_quad_edges(quad, point);
+    if point_in_quad(quad, point, 0.) {
+        -distance
+    } else {
+        distance
+    }
+}
+
+/** Shortest distance from the point to any of the quad's four edges */
+fn distance_to_quad_edges(quad: &RectCorners, point: &Point2D) -> f32 {
+    (0..4)
+        .map(|i| distance_to_segment(point, &quad[i], &quad[(i + 1) % 4]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+fn distance_to_segment(point: &Point2D, a: &Point2D, b: &Point2D) -> f32 {
+    let (x, y) = closest_point_on_segment(point, a, b);
+    distance(point.0, point.1, x, y)
+}
+
+fn closest_point_on_segment(point: &Point2D, a: &Point2D, b: &Point2D) -> Point2D {
+    let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = abx * abx + aby * aby;
+    let t = if length_squared > 0. {
+        (((point.0 - a.0) * abx + (point.1 - a.1) * aby) / length_squared).clamp(0., 1.)
+    } else {
+        0.
+    };
+    (a.0 + t * abx, a.1 + t * aby)
+}
+
+/** The point on the quad's edges nearest to the given point */
+fn closest_point_on_quad_edges(quad: &RectCorners, point: &Point2D) -> Point2D {
+    (0..4)
+        .map(|i| closest_point_on_segment(point, &quad[i], &quad[(i + 1) % 4]))
+        .fold((f32::INFINITY, f32::INFINITY), |best, candidate| {
+            if distance(point.0, point.1, candidate.0, candidate.1)
+                < distance(point.0, point.1, best.0, best.1)
+            {
+                candidate
+            } else {
+                best
+            }
+        })
+}
+
+/** Bilinear interpolation of `(u, v)` across the quad corners */
+fn bilinear_point(quad: &RectCorners, uv: Point2D) -> Point2D {
+    let (u, v) = uv;
+    let [a, b, c, d] = quad;
+    (
+        (1. - u) * (1. - v) * a.0 + u * (1. - v) * b.0 + u * v * c.0 + (1. - u) * v * d.0,
+        (1. - u) * (1. - v) * a.1 + u * (1. - v) * b.1 + u * v * c.1 + (1. - u) * v * d.1,
+    )
+}
+
+/** Twice the signed area of the polygon (shoelace formula); positive for corners
+ordered clockwise on screen, i.e. with y pointing down */
+fn signed_area(polygon: &[Point2D]) -> f32 {
+    (0..polygon.len())
+        .map(|i| cross(polygon[i], polygon[(i + 1) % polygon.len()]))
+        .sum()
+}
+
+/** Whether any corners coincide, or the quad's area is negligible relative to its
+size */
+fn is_degenerate(quad: &RectCorners) -> bool {
+    let (min, max) = bounds(quad);
+    let size_squared = (max.0 - min.0).powi(2) + (max.1 - min.1).powi(2);
+    let repeated_corner =
+        (0..4).any(|i| quad[i] == quad[(i + 1) % 4] || quad[i] == quad[(i + 2) % 4]);
+    repeated_corner || signed_area(quad).abs() <= 1e-6 * size_squared
+}
+
+/** Whether every turn between consecutive edges is in the same direction */
+fn is_convex(quad: &RectCorners) -> bool {
+    let turns: Vec<f32> = (0..4)
+        .map(|i| {
+            let (a, b, c) = (quad[i], quad[(i + 1) % 4], quad[(i + 2) % 4]);
+            cross((b.0 - a.0, b.1 - a.1), (c.0 - b.0, c.1 - b.1))
+        })
+        .collect();
+    turns.iter().all(|t| *t >= 0.) || turns.iter().all(|t| *t <= 0.)
+}
+
+/** Sutherland-Hodgman clipping of an arbitrary polygon against a convex quad */
+fn clip_polygon(subject: &[Point2D], clip: &RectCorners) -> Vec<Point2D> {
+    let orientation = signed_area(clip).signum();
+    let mut output = subject.to_vec();
+    for i in 0..4 {
+        if output.is_empty() {
+            break;
+        }
+        let (a, b) = (clip[i], clip[(i + 1) % 4]);
+        let edge = (b.0 - a.0, b.1 - a.1);
+        let side = |p: Point2D| orientation * cross(edge, (p.0 - a.0, p.1 - a.1));
+        let input = std::mem::take(&mut output);
+        for j in 0..input.len() {
+            let current = input[j];
+            let previous = input[(j + input.len() - 1) % input.len()];
+            let (side_current, side_previous) = (side(current), side(previous));
+            if (side_current >= 0.) != (side_previous >= 0.) {
+                let t = side_previous / (side_previous - side_current);
+                output.push((
+                    previous.0 + t * (current.0 - previous.0),
+                    previous.1 + t * (current.1 - previous.1),
+                ));
+            }
+            if side_current >= 0. {
+                output.push(current);
+            }
+        }
+    }
+    output
+}
+
+fn cross(a: Point2D, b: Point2D) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+/** Inverse of `bilinear_point`: find the `(u, v)` which maps onto the given point.
+Where there are two solutions, the one closest to the `[0, 1]` range is used. */
+fn bilinear_inverse(quad: &RectCorners, point: Point2D) -> Option<Point2D> {
+    let [a, b, c, d] = quad;
+    let e = (b.0 - a.0, b.1 - a.1);
+    let f = (d.0 - a.0, d.1 - a.1);
+    let g = (a.0 - b.0 + c.0 - d.0, a.1 - b.1 + c.1 - d.1);
+    let h = (point.0 - a.0, point.1 - a.1);
+
+    let k2 = cross(g, f);
+    let k1 = cross(e, f) + cross(h, g);
+    let k0 = cross(h, e);
+
+    let u_for_v = |v: f32| {
+        let (denominator_x, denominator_y) = (e.0 + g.0 * v, e.1 + g.1 * v);
+        if denominator_x.abs() > denominator_y.abs() {
+            (h.0 - f.0 * v) / denominator_x
+        } else {
+            (h.1 - f.1 * v) / denominator_y
+        }
+    };
+
+    if k2.abs() < f32::EPSILON * k1.abs().max(1.) {
+        // Edges are parallel; the equation is linear
+        if k1 == 0. {
+            return None;
+        }
+        let v = -k0 / k1;
+        return Some((u_for_v(v), v));
+    }
+
+    let discriminant = k1 * k1 - 4. * k0 * k2;
+    if discriminant < 0. {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let out_of_range = |uv: &Point2D| {
+        let overshoot = |t: f32| (t - t.clamp(0., 1.)).abs();
+        overshoot(uv.0) + overshoot(uv.1)
+    };
+    [(-k1 - root) / (2. * k2), (-k1 + root) / (2. * k2)]
+        .into_iter()
+        .map(|v| (u_for_v(v), v))
+        .min_by(|p, q| out_of_range(p).total_cmp(&out_of_range(q)))
+}
+
+fn distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
+    ((x2 - x1).powf(2.0) + (y2 - y1).powf(2.0)).sqrt()
+}
+
+fn build_transform(src_quad: &RectCorners, dst_quad: &RectCorners) -> Matrix3<f32> {
+    try_build_transform(src_quad, dst_quad).unwrap()
+}
+
+fn try_build_transform(
+    src_quad: &RectCorners,
+    dst_quad: &RectCorners,
+) -> anyhow::Result<Matrix3<f32>> {
+    let (_matrix_a, _matrix_b, result) = build_transform_debug(src_quad, dst_quad);
+    result
+}
+
+/** Build the transform exactly as `build_transform` does, but also return the
+intermediate 8x8 system matrix (`matrix_a`) and the 1x8 destination vector
+(`matrix_b`), for diagnosing singular or otherwise badly-behaved quads.
+
+The coefficients of the transform are `matrix_b * matrix_a^-1`; the result is
+an error if `matrix_a` cannot be inverted. */
+pub fn build_transform_debug(
+    src_quad: &RectCorners,
+    dst_quad: &RectCorners,
+) -> (Matrix8x8, Matrix1x8, anyhow::Result<Matrix3<f32>>) {
+    // Mappings by row - each should have 8 terms
+
+    let r1: [f32; 8] = [
+        src_quad[0].0,
+        src_quad[0].1,
+        1.,
+        0.,
+        0.,
+        0.,
+        -src_quad[0].0 * dst_quad[0].0,
+        -src_quad[0].1 * dst_quad[0].0,
+    ];
+    let r2: [f32; 8] = [
+        0.,
+        0.,
+        0.,
+        src_quad[0].0,
+        src_quad[0].1,
+        1.,
+        -src_quad[0].0 * dst_quad[0].1,
+        -src_quad[0].1 * dst_quad[0].1,
+    ];
+    let r3: [f32; 8] = [
+        src_quad[1].0,
+        src_quad[1].1,
+        1.,
+        0.,
+        0.,
+        0.,
+        -src_quad[1].0 * dst_quad[1].0,
+        -src_quad[1].1 * dst_quad[1].0,
+    ];
+    let r4: [f32; 8] = [
+        0.,
+        0.,
+        0.,
+        src_quad[1].0,
+        src_quad[1].1,
+        1.,
         -src_quad[1].0 * dst_quad[1].1,
         -src_quad[1].1 * dst_quad[1].1,
     ];
@@ -205,114 +3052,2430 @@ fn build_transform(src_quad: &RectCorners, dst_quad: &RectCorners) -> Matrix3<f3
     ];
     let combined = vec![r1, r2, r3, r4, r5, r6, r7, r8].into_iter().flatten();
 
-    let matrix_a = Matrix8x8::from_iterator(combined);
+    let matrix_a = Matrix8x8::from_iterator(combined);
+
+    let dst_quad_elements = vec![
+        dst_quad[0].0,
+        dst_quad[0].1,
+        dst_quad[1].0,
+        dst_quad[1].1,
+        dst_quad[2].0,
+        dst_quad[2].1,
+        dst_quad[3].0,
+        dst_quad[3].1,
+    ]
+    .into_iter();
+
+    let matrix_b = Matrix1x8::from_iterator(dst_quad_elements);
+
+    let result = solve_coefficients(&matrix_a, &matrix_b);
+    (matrix_a, matrix_b, result)
+}
+
+/** Fit a transform to any number (at least 4) of point correspondences by least
+squares, e.g. from a grid of detected markers. See `build_transform_weighted`. */
+pub fn build_transform_lsq(src: &[Point2D], dst: &[Point2D]) -> anyhow::Result<Matrix3<f32>> {
+    build_transform_weighted(src, dst, &vec![1.; src.len()])
+}
+
+/** Fit a transform to any number (at least 4) of point correspondences by weighted
+least squares, so that correspondences with a lower weight (e.g. less confident
+detections) have less influence on the fit.
+
+The points are normalized (centred on the origin, with an average distance of √2)
+before solving, which keeps the normal equations well conditioned in `f32` even for
+pixel coordinates. */
+pub fn build_transform_weighted(
+    src: &[Point2D],
+    dst: &[Point2D],
+    weights: &[f32],
+) -> anyhow::Result<Matrix3<f32>> {
+    if src.len() != dst.len() || src.len() != weights.len() {
+        return Err(anyhow!(
+            "Mismatched lengths: {} source points, {} destination points, {} weights",
+            src.len(),
+            dst.len(),
+            weights.len()
+        ));
+    }
+    if src.len() < 4 {
+        return Err(anyhow!(
+            "At least 4 correspondences are needed, got {}",
+            src.len()
+        ));
+    }
+    if weights.iter().any(|w| !(w.is_finite() && *w >= 0.)) {
+        return Err(anyhow!("Weights must be finite and non-negative"));
+    }
+    fit_normalized(src, dst, weights, |contribution| {
+        (0..src.len()).map(contribution).fold(
+            (Matrix8x8::zeros(), Vector8::zeros()),
+            |(ata, atb), (a, b)| (ata + a, atb + b),
+        )
+    })
+}
+
+/** Same as `build_transform_lsq`, but accumulates the normal equations across
+threads with rayon, for very large correspondence sets (tens of thousands of
+points). The result matches the serial solver to within floating point rounding. */
+#[cfg(feature = "rayon")]
+pub fn build_transform_lsq_par(src: &[Point2D], dst: &[Point2D]) -> anyhow::Result<Matrix3<f32>> {
+    use rayon::prelude::*;
+
+    if src.len() != dst.len() {
+        return Err(anyhow!(
+            "Mismatched lengths: {} source points, {} destination points",
+            src.len(),
+            dst.len()
+        ));
+    }
+    if src.len() < 4 {
+        return Err(anyhow!(
+            "At least 4 correspondences are needed, got {}",
+            src.len()
+        ));
+    }
+    fit_normalized(src, dst, &vec![1.; src.len()], |contribution| {
+        (0..src.len()).into_par_iter().map(contribution).reduce(
+            || (Matrix8x8::zeros(), Vector8::zeros()),
+            |(ata, atb), (a, b)| (ata + a, atb + b),
+        )
+    })
+}
+
+/** Normalize the (already validated) correspondences, sum the normal equations
+using `accumulate` (given a function returning the contribution of the
+correspondence at an index) and solve them, undoing the normalization */
+fn fit_normalized<F>(
+    src: &[Point2D],
+    dst: &[Point2D],
+    weights: &[f32],
+    accumulate: F,
+) -> anyhow::Result<Matrix3<f32>>
+where
+    F: FnOnce(&(dyn Fn(usize) -> (Matrix8x8, Vector8) + Sync)) -> (Matrix8x8, Vector8),
+{
+    let src_normalization = normalization_matrix(src);
+    let dst_normalization = normalization_matrix(dst);
+    let normalize = |matrix: &Matrix3<f32>, (x, y): Point2D| {
+        let p = matrix.transform_point(&Point2::new(x, y));
+        (p.x, p.y)
+    };
+
+    let (ata, atb) = accumulate(&|i| {
+        normal_equations(
+            normalize(&src_normalization, src[i]),
+            normalize(&dst_normalization, dst[i]),
+            weights[i],
+        )
+    });
+
+    let normalized = solve_normal_equations(&ata, &atb)?;
+    let dst_denormalization = dst_normalization
+        .try_inverse()
+        .ok_or_else(|| anyhow!("Destination points are degenerate"))?;
+    let matrix = dst_denormalization * normalized * src_normalization;
+    Ok(matrix / matrix[(2, 2)])
+}
+
+/** Fit the affine transform (6 degrees of freedom, no perspective) which maps the
+three source points exactly onto the three destination points, for when only three
+correspondences are available. Errors if the source points are (nearly) collinear. */
+pub fn build_affine_transform(
+    src: [Point2D; 3],
+    dst: [Point2D; 3],
+) -> anyhow::Result<Matrix3<f32>> {
+    let (min, max) = bounds(&src);
+    let size_squared = (max.0 - min.0).powi(2) + (max.1 - min.1).powi(2);
+    if signed_area(&src).abs() <= 1e-6 * size_squared {
+        return Err(anyhow!("Source points {src:?} are collinear"));
+    }
+    fit_affine(&src, &dst).ok_or_else(|| anyhow!("Source points {src:?} are collinear"))
+}
+
+/** Similarity transform which moves the points' centroid to the origin and scales
+them to an average distance of √2 from it */
+fn normalization_matrix(points: &[Point2D]) -> Matrix3<f32> {
+    let n = points.len() as f32;
+    let (cx, cy) = points
+        .iter()
+        .fold((0., 0.), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+    let mean_distance = points
+        .iter()
+        .map(|(x, y)| distance(*x, *y, cx, cy))
+        .sum::<f32>()
+        / n;
+    let scale = if mean_distance > 0. {
+        std::f32::consts::SQRT_2 / mean_distance
+    } else {
+        1.
+    };
+    Matrix3::new(scale, 0., -scale * cx, 0., scale, -scale * cy, 0., 0., 1.)
+}
+
+/** The contribution of a single weighted correspondence to AᵀWA and AᵀWb */
+fn normal_equations(src: Point2D, dst: Point2D, weight: f32) -> (Matrix8x8, Vector8) {
+    let ((x, y), (u, v)) = (src, dst);
+    let row_u = Vector8::from([x, y, 1., 0., 0., 0., -x * u, -y * u]);
+    let row_v = Vector8::from([0., 0., 0., x, y, 1., -x * v, -y * v]);
+    (
+        (row_u * row_u.transpose() + row_v * row_v.transpose()) * weight,
+        (row_u * u + row_v * v) * weight,
+    )
+}
+
+fn solve_normal_equations(ata: &Matrix8x8, atb: &Vector8) -> anyhow::Result<Matrix3<f32>> {
+    let h = ata
+        .lu()
+        .solve(atb)
+        .ok_or_else(|| anyhow!("Correspondences are degenerate; cannot fit a transform"))?;
+    Ok(Matrix3::new(
+        h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7], 1.,
+    ))
+}
+
+/** Least squares affine fit to three or more correspondences; `None` if the source
+points are collinear */
+fn fit_affine(src: &[Point2D], dst: &[Point2D]) -> Option<Matrix3<f32>> {
+    // Solve relative to the source centroid, to keep the normal equations well
+    // conditioned for large coordinates
+    let n = src.len() as f32;
+    let (cx, cy) = src
+        .iter()
+        .fold((0., 0.), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+
+    let mut ata = Matrix3::<f32>::zeros();
+    let mut atu = na::Vector3::<f32>::zeros();
+    let mut atv = na::Vector3::<f32>::zeros();
+    for ((x, y), (u, v)) in src.iter().zip(dst.iter()) {
+        let row = na::Vector3::new(x - cx, y - cy, 1.);
+        ata += row * row.transpose();
+        atu += row * *u;
+        atv += row * *v;
+    }
+    let lu = ata.lu();
+    let (first, second) = (lu.solve(&atu)?, lu.solve(&atv)?);
+
+    let centred = Matrix3::new(
+        first[0], first[1], first[2], second[0], second[1], second[2], 0., 0., 1.,
+    );
+    Some(centred * Matrix3::new(1., 0., -cx, 0., 1., -cy, 0., 0., 1.))
+}
+
+fn solve_coefficients(matrix_a: &Matrix8x8, matrix_b: &Matrix1x8) -> anyhow::Result<Matrix3<f32>> {
+    // Solve for Ah = B
+    let inverse = matrix_a
+        .try_inverse()
+        .ok_or_else(|| anyhow!("System matrix is singular; check the quad corners"))?;
+    let coefficients = matrix_b * inverse;
+
+    // Create a new 3x3 transform matrix using the elements from above
+    Ok(Matrix3::new(
+        coefficients[0],
+        coefficients[1],
+        coefficients[2],
+        coefficients[3],
+        coefficients[4],
+        coefficients[5],
+        coefficients[6],
+        coefficients[7],
+        1.,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::*;
+
+    use super::RectCorners;
+
+    /** The example quads from
+    https://github.com/jlouthan/perspective-transform#basic-usage, shared by most
+    of the tests */
+    const EXAMPLE_SRC: RectCorners = [(158., 64.), (494., 69.), (495., 404.), (158., 404.)];
+    const EXAMPLE_DST: RectCorners = [(100., 500.), (152., 564.), (148., 604.), (100., 560.)];
+
+    #[test]
+    fn test_get_transform_matrix() {
+        // numbers as per https://github.com/jlouthan/perspective-transform#basic-usage
+
+        let src_quad = [158., 64., 494., 69., 495., 404., 158., 404.];
+        let src_quad: RectCorners = [
+            (src_quad[0], src_quad[1]),
+            (src_quad[2], src_quad[3]),
+            (src_quad[4], src_quad[5]),
+            (src_quad[6], src_quad[7]),
+        ];
+
+        let dst_quad = [100., 500., 152., 564., 148., 604., 100., 560.];
+        let dst_quad: RectCorners = [
+            (dst_quad[0], dst_quad[1]),
+            (dst_quad[2], dst_quad[3]),
+            (dst_quad[4], dst_quad[5]),
+            (dst_quad[6], dst_quad[7]),
+        ];
+
+        let transform_matrix = build_transform(&src_quad, &dst_quad);
+
+        let src_point = (250., 120.);
+
+        let result = {
+            let (x, y) = (src_point.0, src_point.1);
+            let nalgebra_point = nalgebra::Point2::new(x, y);
+
+            let transformed = transform_matrix.transform_point(&nalgebra_point);
+            (transformed.x, transformed.y)
+        };
+
+        assert_eq!(
+            (result.0.round(), result.1.round()),
+            (117.275_21_f32.round(), 530.920_2_f32.round(),),
+        );
+    }
+
+    #[test]
+    fn test_get_transform_matrix_simple() {
+        // numbers as per https://github.com/jlouthan/perspective-transform#basic-usage
+
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let dst_quad: RectCorners = [(1., 2.), (1., 4.), (3., 4.), (3., 2.)];
+
+        let transform_matrix = build_transform(&src_quad, &dst_quad);
+
+        // The transform matrix is a little different to the example in https://blog.mbedded.ninja/mathematics/geometry/projective-transformations/
+        // because their point order is somehow different. The result is the same.
+        // assert_eq!(
+        //     transform_matrix,
+        //     Matrix3::new(2., 0., 1., 0., 2., 2., 0., 0., 1.)
+        // );
+
+        let src_point = (0.5, 0.5);
+
+        let result = {
+            let (x, y) = (src_point.0, src_point.1);
+            let nalgebra_point = nalgebra::Point2::new(x, y);
+
+            let transformed = transform_matrix.transform_point(&nalgebra_point);
+            (transformed.x, transformed.y)
+        };
+
+        assert_eq!(result, (2., 3.));
+    }
+
+    #[test]
+    fn test_build_transform_debug_recombines() {
+        let (matrix_a, matrix_b, result) = build_transform_debug(&EXAMPLE_SRC, &EXAMPLE_DST);
+        let transform_matrix = result.unwrap();
+
+        let coefficients = matrix_b * matrix_a.try_inverse().unwrap();
+        let recombined = Matrix3::new(
+            coefficients[0],
+            coefficients[1],
+            coefficients[2],
+            coefficients[3],
+            coefficients[4],
+            coefficients[5],
+            coefficients[6],
+            coefficients[7],
+            1.,
+        );
+
+        assert_eq!(recombined, transform_matrix);
+        assert_eq!(
+            build_transform(&EXAMPLE_SRC, &EXAMPLE_DST),
+            transform_matrix
+        );
+    }
+
+    #[test]
+    fn test_build_transform_debug_singular() {
+        let src_quad: RectCorners = [(1., 1.), (1., 1.), (1., 1.), (1., 1.)];
+
+        let (_matrix_a, _matrix_b, result) = build_transform_debug(&src_quad, &DEFAULT_DST_QUAD);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_transform_normalized_center() {
+        let src_quad: RectCorners = [(10., 10.), (110., 10.), (110., 60.), (10., 60.)];
+        let dst_quad: RectCorners = [(0., 0.), (200., 0.), (200., 100.), (0., 100.)];
+        let transformer = QuadTransformer::new(Some(src_quad), Some(dst_quad), None);
+
+        let (x, y) = transformer.transform_normalized((0.5, 0.5)).unwrap();
+
+        assert!((x - 100.).abs() < 0.01);
+        assert!((y - 50.).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_from_image_size() {
+        let transformer = QuadTransformer::from_image_size(640., 480., EXAMPLE_DST, None).unwrap();
+
+        let image_corners = [(0., 0.), (640., 0.), (640., 480.), (0., 480.)];
+        for (corner, expected) in image_corners.iter().zip(EXAMPLE_DST.iter()) {
+            let (x, y) = transformer.transform(corner).unwrap();
+            assert!((x - expected.0).abs() < 0.01);
+            assert!((y - expected.1).abs() < 0.01);
+        }
+
+        assert!(QuadTransformer::from_image_size(0., 480., EXAMPLE_DST, None).is_err());
+    }
+
+    #[test]
+    fn test_src_contains() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), None, None);
+
+        assert!(transformer.src_contains(&(300., 200.), 0.));
+        assert!(!transformer.src_contains(&(100., 200.), 0.));
+        assert!(!transformer.src_contains(&(300., 420.), 0.));
+        assert!(transformer.src_contains(&(300., 420.), 20.));
+
+        let not_ready = QuadTransformer::new(None, None, None);
+        assert!(!not_ready.src_contains(&(300., 200.), 0.));
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn test_transform_many_simd_matches_scalar() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let points: Vec<Point2D> = (0..11)
+            .map(|i| (150. + i as f32 * 31., 60. + i as f32 * 29.))
+            .collect();
+
+        let scalar = transformer.transform_many(&points).unwrap();
+        let simd = transformer.transform_many_simd(&points).unwrap();
+
+        assert_eq!(scalar.len(), simd.len());
+        for (a, b) in scalar.iter().zip(simd.iter()) {
+            assert!((a.0 - b.0).abs() < 1e-3);
+            assert!((a.1 - b.1).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_transformed_corners() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let corners = transformer.transformed_corners().unwrap();
+
+        for (corner, expected) in corners.iter().zip(transformer.dst_quad.unwrap().iter()) {
+            assert!((corner.0 - expected.0).abs() < 0.01);
+            assert!((corner.1 - expected.1).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_pixel_rounding_modes() {
+        assert_eq!(PixelRounding::Round.apply(2.5), 3);
+        assert_eq!(PixelRounding::Floor.apply(2.5), 2);
+        assert_eq!(PixelRounding::Ceil.apply(2.5), 3);
+        assert_eq!(PixelRounding::Truncate.apply(2.5), 2);
+
+        assert_eq!(PixelRounding::Round.apply(-2.5), -3);
+        assert_eq!(PixelRounding::Floor.apply(-2.5), -3);
+        assert_eq!(PixelRounding::Ceil.apply(-2.5), -2);
+        assert_eq!(PixelRounding::Truncate.apply(-2.5), -2);
+    }
+
+    #[test]
+    fn test_transform_to_pixels() {
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let dst_quad: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        let transformer = QuadTransformer::new(Some(src_quad), Some(dst_quad), None);
+
+        let point = (0.25, 0.25);
+        assert_eq!(
+            transformer
+                .transform_to_pixels(&point, PixelRounding::Round)
+                .unwrap(),
+            (3, 3)
+        );
+        assert_eq!(
+            transformer
+                .transform_to_pixels(&point, PixelRounding::Floor)
+                .unwrap(),
+            (2, 2)
+        );
+        assert_eq!(
+            transformer
+                .transform_to_pixels(&point, PixelRounding::Ceil)
+                .unwrap(),
+            (3, 3)
+        );
+        assert_eq!(
+            transformer
+                .transform_to_pixels(&point, PixelRounding::Truncate)
+                .unwrap(),
+            (2, 2)
+        );
+    }
+
+    #[test]
+    fn test_filter_points_inside_checked_before_calibration() {
+        let mut transformer = QuadTransformer::new(None, None, Some(0.1));
+        let points = [(0.5, 0.5), (2., 2.)];
+
+        assert!(transformer.filter_points_inside_checked(&points).is_err());
+
+        transformer.set_new_quad(&DEFAULT_DST_QUAD, None);
+        assert_eq!(
+            transformer.filter_points_inside_checked(&points).unwrap(),
+            vec![(0.5, 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_uv_round_trip() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        for uv in [(0., 0.), (0.5, 0.5), (0.25, 0.8), (1., 0.1)] {
+            let dst = transformer.uv_to_dst(uv).unwrap();
+            let (u, v) = transformer.dst_to_uv(dst).unwrap();
+            assert!((u - uv.0).abs() < 1e-3, "{u} vs {}", uv.0);
+            assert!((v - uv.1).abs() < 1e-3, "{v} vs {}", uv.1);
+        }
+    }
+
+    #[test]
+    fn test_transform_and_dedup() {
+        let src_quad: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let transformer = QuadTransformer::new(Some(src_quad), None, None);
+
+        let points = [(50., 50.), (50.5, 50.), (10., 90.)];
+        let deduped = transformer.transform_and_dedup(&points, 0.01).unwrap();
+
+        assert_eq!(deduped.len(), 2);
+        assert!((deduped[0].0 - 0.5025).abs() < 1e-4);
+        assert!((deduped[0].1 - 0.5).abs() < 1e-4);
+        assert!((deduped[1].0 - 0.1).abs() < 1e-4);
+        assert!((deduped[1].1 - 0.9).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_rotated_rect() {
+        let axis_aligned = rotated_rect((5., 5.), (4., 2.), 0.);
+        assert_eq!(axis_aligned, [(3., 4.), (7., 4.), (7., 6.), (3., 6.)]);
+
+        let rotated = rotated_rect((5., 5.), (4., 2.), std::f32::consts::FRAC_PI_2);
+        let expected: RectCorners = [(6., 3.), (6., 7.), (4., 7.), (4., 3.)];
+        for (corner, expected) in rotated.iter().zip(expected.iter()) {
+            assert!((corner.0 - expected.0).abs() < 1e-5);
+            assert!((corner.1 - expected.1).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_transform_slice_in_place() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let mut points = vec![(250., 120.), (300., 300.), (480., 80.)];
+        let expected = transformer.transform_many(&points).unwrap();
+
+        transformer.transform_slice_in_place(&mut points).unwrap();
+
+        assert_eq!(points, expected);
+    }
+
+    #[test]
+    fn test_fallback_identity() {
+        let degenerate: RectCorners = [(1., 1.), (1., 1.), (1., 1.), (1., 1.)];
+
+        assert!(QuadTransformer::builder()
+            .with_src_quad(degenerate)
+            .build()
+            .is_err());
+
+        let mut transformer = QuadTransformer::builder()
+            .with_src_quad(degenerate)
+            .with_fallback_identity(true)
+            .build()
+            .unwrap();
+        assert!(transformer.is_ready());
+        assert_eq!(transformer.transform(&(3., 4.)).unwrap(), (3., 4.));
+
+        transformer.set_new_quad(&degenerate, None);
+        assert_eq!(transformer.transform(&(5., 6.)).unwrap(), (5., 6.));
+    }
+
+    #[test]
+    fn test_transform_polygon_clipped() {
+        let src_quad: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        let transformer = QuadTransformer::new(Some(src_quad), None, None);
+
+        // A square overlapping the right-hand half of the source quad
+        let polygon = [(5., 2.), (15., 2.), (15., 8.), (5., 8.)];
+        let clipped = transformer.transform_polygon_clipped(&polygon).unwrap();
+
+        let expected = [(0.5, 0.2), (1., 0.2), (1., 0.8), (0.5, 0.8)];
+        assert_eq!(clipped.len(), expected.len());
+        for expected in expected.iter() {
+            assert!(clipped
+                .iter()
+                .any(|p| (p.0 - expected.0).abs() < 1e-5 && (p.1 - expected.1).abs() < 1e-5));
+        }
+
+        let outside = [(20., 20.), (30., 20.), (30., 30.)];
+        assert!(transformer
+            .transform_polygon_clipped(&outside)
+            .unwrap()
+            .is_empty());
+
+        // A non-finite vertex is an error rather than silently dropped
+        let broken = [(5., 2.), (f32::NAN, 2.), (15., 8.), (5., 8.)];
+        assert!(transformer.transform_polygon_clipped(&broken).is_err());
+    }
+
+    #[test]
+    fn test_axis_scales_at() {
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let dst_quad: RectCorners = [(0., 0.), (4., 0.), (4., 2.), (0., 2.)];
+        let transformer = QuadTransformer::new(Some(src_quad), Some(dst_quad), None);
+
+        let (scale_x, scale_y) = transformer.axis_scales_at(&(0.3, 0.6)).unwrap();
+
+        assert!((scale_x - 4.).abs() < 1e-4);
+        assert!((scale_y - 2.).abs() < 1e-4);
+        assert!((scale_x / scale_y - 2.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_reset_to_default_dst() {
+        let src_quad: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let dst_quad: RectCorners = [(0., 0.), (640., 0.), (640., 480.), (0., 480.)];
+        let mut transformer = QuadTransformer::new(Some(src_quad), Some(dst_quad), None);
+        let (x, y) = transformer.transform(&(50., 50.)).unwrap();
+        assert!((x - 320.).abs() < 0.01 && (y - 240.).abs() < 0.01);
+
+        transformer.reset_to_default_dst().unwrap();
+
+        assert!(transformer.dst_quad.is_none());
+        let (x, y) = transformer.transform(&(50., 50.)).unwrap();
+        assert!((x - 0.5).abs() < 1e-5 && (y - 0.5).abs() < 1e-5);
+        let (x, y) = transformer.transform(&(100., 100.)).unwrap();
+        assert!((x - 1.).abs() < 1e-5 && (y - 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_corners_from_slice() {
+        let points = [(0., 0.), (1., 0.), (1., 1.), (0., 1.), (0.5, 0.5)];
+
+        assert!(corners_from_slice(&points[..3]).is_err());
+        assert_eq!(corners_from_slice(&points[..4]).unwrap(), DEFAULT_DST_QUAD);
+        let error = corners_from_slice(&points).unwrap_err();
+        assert!(error.to_string().contains('5'));
+    }
+
+    #[test]
+    fn test_matrix_opencv() {
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let dst_quad: RectCorners = [(1., 2.), (1., 4.), (3., 4.), (3., 2.)];
+        let transformer = QuadTransformer::new(Some(src_quad), Some(dst_quad), None);
+
+        let values = transformer.matrix_opencv().unwrap();
+
+        let expected = [0., 2., 1., 2., 0., 2., 0., 0., 1.];
+        for (value, expected) in values.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-5, "{values:?}");
+        }
+        assert!(QuadTransformer::new(None, None, None)
+            .matrix_opencv()
+            .is_none());
+    }
+
+    #[test]
+    fn test_with_output_scale() {
+        let src_quad: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        let mut transformer = QuadTransformer::new(Some(src_quad), None, None);
+        let pivot = (0.5, 0.5);
+        let point = (8., 3.);
+
+        let before = transformer.transform(&point).unwrap();
+        transformer.with_output_scale(3., pivot);
+        let after = transformer.transform(&point).unwrap();
+
+        let distance_before = distance(pivot.0, pivot.1, before.0, before.1);
+        let distance_after = distance(pivot.0, pivot.1, after.0, after.1);
+        assert!((distance_after - 3. * distance_before).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_with_input_scale() {
+        let mut transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let expected = transformer.transform(&(320., 240.)).unwrap();
+
+        transformer.with_input_scale(640., 480.);
+        let (x, y) = transformer.transform(&(0.5, 0.5)).unwrap();
+
+        assert!((x - expected.0).abs() < 0.01);
+        assert!((y - expected.1).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_transform_in_source_region() {
+        let src_quad: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        let transformer = QuadTransformer::new(Some(src_quad), None, None);
+
+        let points = [(5., 5.), (-5., 5.), (10.5, 2.), (2., 8.), (20., 20.)];
+        let result = transformer.transform_in_source_region(&points, 1.).unwrap();
+
+        let indices: Vec<usize> = result.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, vec![0, 2, 3]);
+        for (i, transformed) in result {
+            assert_eq!(transformed, transformer.transform(&points[i]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let original = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), Some(0.1));
+
+        let mut rebuilt = QuadTransformer::new(None, None, Some(0.1));
+        rebuilt.set_new_quad(&EXAMPLE_SRC, Some(EXAMPLE_DST));
+        assert!(original.approx_eq(&rebuilt, 1e-6));
+
+        // Persisting and reloading the calibration gives an equivalent transformer
+        let reloaded = QuadTransformer::from_bytes(&original.to_bytes()).unwrap();
+        assert!(original.approx_eq(&reloaded, 1e-6));
+
+        let mut moved = EXAMPLE_SRC;
+        moved[2].0 += 10.;
+        let different = QuadTransformer::new(Some(moved), Some(EXAMPLE_DST), Some(0.1));
+        assert!(!original.approx_eq(&different, 1e-6));
+
+        let different_margin = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        assert!(!original.approx_eq(&different_margin, 1e-6));
+    }
+
+    #[test]
+    fn test_validation_rms() {
+        let src_quad: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let transformer = QuadTransformer::new(Some(src_quad), None, None);
+
+        let src = [(10., 10.), (50., 50.), (90., 20.)];
+        let perfect = [(0.1, 0.1), (0.5, 0.5), (0.9, 0.2)];
+        let noisy = [(0.1, 0.13), (0.46, 0.5), (0.9, 0.2)];
+
+        assert!(transformer.validation_rms(&src, &perfect).unwrap() < 1e-5);
+        let rms = transformer.validation_rms(&src, &noisy).unwrap();
+        assert!((rms - (0.0025_f32 / 3.).sqrt()).abs() < 1e-4);
+
+        assert!(transformer.validation_rms(&src, &noisy[..2]).is_err());
+    }
+
+    #[test]
+    fn test_transform_vector_at() {
+        // Rotate by 90 degrees, scale by 2 and translate by (5, 5)
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let dst_quad: RectCorners = [(5., 5.), (5., 7.), (3., 7.), (3., 5.)];
+        let transformer = QuadTransformer::new(Some(src_quad), Some(dst_quad), None);
+
+        for base_point in [(0., 0.), (0.5, 0.25), (3., -2.)] {
+            let (x, y) = transformer
+                .transform_vector_at(&base_point, &(1., 0.))
+                .unwrap();
+            assert!(x.abs() < 1e-4 && (y - 2.).abs() < 1e-4);
+            let (x, y) = transformer
+                .transform_vector_at(&base_point, &(0., 1.5))
+                .unwrap();
+            assert!((x + 3.).abs() < 1e-4 && y.abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let original = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), Some(0.1));
+
+        let restored = QuadTransformer::from_bytes(&original.to_bytes()).unwrap();
+
+        assert!(original.approx_eq(&restored, 0.));
+        assert_eq!(restored.src_quad, Some(EXAMPLE_SRC));
+        assert_eq!(
+            restored.transform(&(250., 120.)).unwrap(),
+            original.transform(&(250., 120.)).unwrap()
+        );
+
+        let not_ready = QuadTransformer::new(None, None, None);
+        let restored = QuadTransformer::from_bytes(&not_ready.to_bytes()).unwrap();
+        assert!(!restored.is_ready());
+        assert!(restored.dst_quad.is_none());
+        assert!(restored.ignore_outside_margin.is_none());
+
+        let passthrough = QuadTransformer::builder()
+            .with_not_ready_policy(NotReadyPolicy::Passthrough)
+            .build()
+            .unwrap();
+        let restored = QuadTransformer::from_bytes(&passthrough.to_bytes()).unwrap();
+        assert_eq!(restored.transform(&(3., 4.)).unwrap(), (3., 4.));
+
+        let banded = QuadTransformer::builder()
+            .with_src_quad(EXAMPLE_SRC)
+            .with_edge_band(5.)
+            .build()
+            .unwrap();
+        let restored = QuadTransformer::from_bytes(&banded.to_bytes()).unwrap();
+        assert!(restored.approx_eq(&banded, 0.));
+        assert_eq!(restored.classify_point(&(0.98, 0.5)), PointZone::EdgeBand);
+        assert!(!restored.approx_eq(&QuadTransformer::new(Some(EXAMPLE_SRC), None, None), 0.));
+
+        for policy in [NanPolicy::Skip, NanPolicy::Fail, NanPolicy::PassThrough] {
+            let original = QuadTransformer::builder()
+                .with_src_quad(EXAMPLE_SRC)
+                .with_nan_policy(policy)
+                .build()
+                .unwrap();
+            let restored = QuadTransformer::from_bytes(&original.to_bytes()).unwrap();
+            assert_eq!(restored.nan_policy, policy);
+        }
+
+        // Options which only matter when the matrix is rebuilt
+        let wide_src: RectCorners = [(0., 0.), (200., 0.), (200., 100.), (0., 100.)];
+        let options = QuadTransformer::builder()
+            .with_src_quad(wide_src)
+            .with_aspect_mode(AspectMode::Fill)
+            .build()
+            .unwrap();
+        let mut restored = QuadTransformer::from_bytes(&options.to_bytes()).unwrap();
+        assert_eq!(restored.aspect_mode, AspectMode::Fill);
+        restored.set_new_quad(&wide_src, None);
+        let (x, _) = restored.transform(&(0., 50.)).unwrap();
+        assert!((x + 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_from_bytes_invalid() {
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let bytes = QuadTransformer::new(Some(src_quad), None, None).to_bytes();
+
+        assert!(QuadTransformer::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(QuadTransformer::from_bytes(&bytes[..2]).is_err());
+        assert!(QuadTransformer::from_bytes(b"garbage garbage").is_err());
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[3] = 99;
+        assert!(QuadTransformer::from_bytes(&wrong_version).is_err());
+
+        // Version 1 had no options byte
+        let mut version_1 = bytes.clone();
+        version_1[3] = 1;
+        version_1.remove(5);
+        version_1.truncate(version_1.len() - 4);
+        let restored = QuadTransformer::from_bytes(&version_1).unwrap();
+        assert_eq!(restored.src_quad, Some(src_quad));
+        assert_eq!(restored.aspect_mode, AspectMode::Stretch);
+    }
+
+    #[test]
+    fn test_containment_with_rotated_dst_quad() {
+        // The same rectangle as usual, but listed starting from the "right bottom"
+        let dst_quad: RectCorners = [(200., 100.), (0., 100.), (0., 0.), (200., 0.)];
+        let transformer = QuadTransformer::new(None, Some(dst_quad), Some(5.));
+
+        let points = [
+            (100., 50.),
+            (203., 98.),
+            (-3., 2.),
+            (-10., 50.),
+            (100., 110.),
+        ];
+        assert_eq!(
+            transformer.filter_points_inside(&points).unwrap(),
+            vec![(100., 50.), (203., 98.), (-3., 2.)]
+        );
+    }
+
+    #[test]
+    fn test_transform_screen() {
+        let src_quad: RectCorners = [(0., 0.), (1920., 0.), (1920., 1080.), (0., 1080.)];
+        let transformer = QuadTransformer::new(Some(src_quad), None, None);
+
+        let (x, y) = transformer.transform_screen(&(480., 270.), 2.).unwrap();
+
+        assert!((x - 0.5).abs() < 1e-5);
+        assert!((y - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let svg = transformer.to_svg(800, 600).unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"800\" height=\"600\""));
+        assert!(svg.contains("points=\"100,500 152,564 148,604 100,560\""));
+        assert!(svg.contains("points=\"158,64 494,69 495,404 158,404\""));
+        assert_eq!(svg.matches("<polyline").count(), 10);
+
+        assert!(QuadTransformer::new(None, None, None)
+            .to_svg(800, 600)
+            .is_none());
+    }
+
+    #[test]
+    fn test_coordinate_system() {
+        let src_quad: RectCorners = [(0., 0.), (640., 0.), (640., 480.), (0., 480.)];
+        let top_left = (0., 0.);
+
+        let y_down = QuadTransformer::builder()
+            .with_src_quad(src_quad)
+            .build()
+            .unwrap();
+        let (_, y) = y_down.transform(&top_left).unwrap();
+        assert!(y.abs() < 1e-5);
+
+        let y_up = QuadTransformer::builder()
+            .with_src_quad(src_quad)
+            .with_coordinate_system(CoordinateSystem::MathYUp)
+            .build()
+            .unwrap();
+        let (x, y) = y_up.transform(&top_left).unwrap();
+        assert!(x.abs() < 1e-5);
+        assert!((y - 1.).abs() < 1e-5);
+        let (_, y) = y_up.transform(&(320., 480.)).unwrap();
+        assert!(y.abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_with_perturbed_corner() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), Some(0.1));
+
+        let perturbed = transformer.with_perturbed_corner(2, (3., -2.)).unwrap();
+
+        let mut nudged = EXAMPLE_SRC;
+        nudged[2] = (498., 402.);
+        let fresh = QuadTransformer::new(Some(nudged), Some(EXAMPLE_DST), Some(0.1));
+        assert!(perturbed.approx_eq(&fresh, 1e-6));
+        assert_eq!(perturbed.src_quad, Some(nudged));
+        assert!(!perturbed.approx_eq(&transformer, 1e-6));
+
+        assert!(transformer.with_perturbed_corner(4, (1., 1.)).is_err());
+    }
+
+    #[test]
+    fn test_transformed_center() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let center = transformer.transformed_center().unwrap();
+
+        let src_centroid = (
+            (158. + 494. + 495. + 158.) / 4.,
+            (64. + 69. + 404. + 404.) / 4.,
+        );
+        assert_eq!(center, transformer.transform(&src_centroid).unwrap());
+
+        let dst_centroid = quad_centroid(&EXAMPLE_DST);
+        assert!(distance(center.0, center.1, dst_centroid.0, dst_centroid.1) > 0.1);
+    }
+
+    #[test]
+    fn test_front_only_mode() {
+        // A strong perspective, with the top edge of the source squashed in the destination
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let dst_quad: RectCorners = [(0.4, 0.), (0.6, 0.), (1., 1.), (0., 1.)];
+        let builder = QuadTransformer::builder()
+            .with_src_quad(src_quad)
+            .with_dst_quad(dst_quad);
+        let standard = builder.clone().build().unwrap();
+        let front_only = builder
+            .with_transform_mode(TransformMode::FrontOnly)
+            .build()
+            .unwrap();
+
+        let inside = (0.5, 0.5);
+        assert_eq!(
+            standard.transform(&inside).unwrap(),
+            front_only.transform(&inside).unwrap()
+        );
+
+        let behind = (0.5, 3.);
+        let (_, _, w) = standard.transform_homogeneous(&behind).unwrap();
+        assert!(w < 0.);
+        assert!(standard.transform(&behind).is_ok());
+        let error = front_only.transform(&behind).unwrap_err();
+        assert!(error.to_string().contains("behind"));
+        assert!(front_only.transform_many(&[inside, behind]).is_err());
+    }
+
+    #[test]
+    fn test_dst_triangles() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let triangles = transformer.dst_triangles().unwrap();
+
+        for vertex in triangles.iter().flatten() {
+            assert!(EXAMPLE_DST.contains(vertex));
+        }
+        let quad_winding = signed_area(&EXAMPLE_DST).signum();
+        for triangle in triangles.iter() {
+            assert_eq!(signed_area(triangle).signum(), quad_winding);
+        }
+        assert!(QuadTransformer::new(None, None, None)
+            .dst_triangles()
+            .is_none());
+    }
+
+    #[test]
+    fn test_build_transform_weighted() {
+        let exact = build_transform(&EXAMPLE_SRC, &EXAMPLE_DST);
+        let apply = |matrix: &Matrix3<f32>, (x, y): Point2D| {
+            let p = matrix.transform_point(&Point2::new(x, y));
+            (p.x, p.y)
+        };
+
+        let src = [
+            (158., 64.),
+            (494., 69.),
+            (495., 404.),
+            (158., 404.),
+            (300., 200.),
+            (400., 100.),
+        ];
+        let mut dst: Vec<Point2D> = src.iter().map(|p| apply(&exact, *p)).collect();
+        // The last correspondence is an outlier
+        dst[5].0 += 20.;
+        dst[5].1 -= 15.;
+
+        let inlier_error = |matrix: &Matrix3<f32>| {
+            src[..5]
+                .iter()
+                .zip(dst[..5].iter())
+                .map(|(s, d)| {
+                    let p = apply(matrix, *s);
+                    distance(p.0, p.1, d.0, d.1)
+                })
+                .fold(0., f32::max)
+        };
+
+        let unweighted = build_transform_lsq(&src, &dst).unwrap();
+        let weights = [1., 1., 1., 1., 1., 0.001];
+        let weighted = build_transform_weighted(&src, &dst, &weights).unwrap();
+
+        assert!(inlier_error(&weighted) < 0.1);
+        assert!(inlier_error(&weighted) < inlier_error(&unweighted));
+
+        assert!(build_transform_weighted(&src, &dst, &weights[..5]).is_err());
+        assert!(build_transform_lsq(&src[..3], &dst[..3]).is_err());
+    }
+
+    #[test]
+    fn test_classify_point() {
+        let dst_quad: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let transformer = QuadTransformer::builder()
+            .with_dst_quad(dst_quad)
+            .with_ignore_outside_margin(10.)
+            .with_edge_band(5.)
+            .build()
+            .unwrap();
+
+        assert_eq!(transformer.classify_point(&(50., 50.)), PointZone::Inside);
+        assert_eq!(transformer.classify_point(&(97., 50.)), PointZone::EdgeBand);
+        assert_eq!(transformer.classify_point(&(50., 2.)), PointZone::EdgeBand);
+        assert_eq!(
+            transformer.classify_point(&(105., 50.)),
+            PointZone::OutsideTolerated
+        );
+        assert_eq!(
+            transformer.classify_point(&(50., -15.)),
+            PointZone::Rejected
+        );
+    }
+
+    #[test]
+    fn test_builder_validate() {
+        let good =
+            QuadTransformer::builder().with_src_quad([(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        assert!(good.validate().is_empty());
+        let math = good.with_coordinate_system(CoordinateSystem::MathYUp);
+        assert!(math.validate().is_empty());
+        let explicit = math.with_dst_quad(CoordinateSystem::MathYUp.default_dst_quad());
+        assert!(explicit.validate().is_empty());
+        // A y down destination quad is mirrored once y points up
+        let mirrored = explicit.with_dst_quad(CoordinateSystem::ScreenYDown.default_dst_quad());
+        assert_eq!(mirrored.validate(), vec![ValidationIssue::Reflected]);
+
+        // Concave, and wound the opposite way to the default destination quad
+        let bad_src: RectCorners = [(0., 0.), (0., 10.), (3., 3.), (10., 0.)];
+        let issues = QuadTransformer::builder().with_src_quad(bad_src).validate();
+        assert_eq!(
+            issues,
+            vec![
+                ValidationIssue::NonConvex {
+                    role: QuadRole::Source
+                },
+                ValidationIssue::Reflected
+            ]
+        );
+
+        let builder = QuadTransformer::builder()
+            .with_src_points(&[(0., 0.), (1., 0.), (1., 1.)])
+            .with_dst_quad([(0., 0.), (1., 0.), (1., 0.), (0., 1.)]);
+        assert_eq!(
+            builder.validate(),
+            vec![
+                ValidationIssue::WrongCornerCount {
+                    role: QuadRole::Source,
+                    count: 3
+                },
+                ValidationIssue::Degenerate {
+                    role: QuadRole::Destination
+                }
+            ]
+        );
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn test_transform_named() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), None, None);
+
+        let anchors = vec![
+            (String::from("stage_left"), (160., 230.)),
+            (String::from("center"), (326., 235.)),
+        ];
+        let transformed = transformer.transform_named(&anchors).unwrap();
+
+        assert_eq!(transformed.len(), 2);
+        for ((name, point), (transformed_name, transformed_point)) in
+            anchors.iter().zip(transformed.iter())
+        {
+            assert_eq!(name, transformed_name);
+            assert_eq!(*transformed_point, transformer.transform(point).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_src_quad_from_points() {
+        let points = [(12., 40.), (30., 5.), (55., 22.), (20., 61.)];
+
+        let quad = src_quad_from_points(&points, 2.).unwrap();
+
+        assert_eq!(quad, [(10., 3.), (57., 3.), (57., 63.), (10., 63.)]);
+        for point in points.iter() {
+            assert!(point_in_quad(&quad, point, 0.));
+        }
+        assert!(src_quad_from_points(&[], 2.).is_err());
+    }
+
+    #[test]
+    fn test_transform_fixed() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let point = (250., 120.);
+
+        let (fixed_x, fixed_y) = transformer.transform_fixed(&point).unwrap();
+
+        let (x, y) = transformer.transform(&point).unwrap();
+        let one = 65536.;
+        assert!((fixed_x as f32 / one - x).abs() <= 0.5 / one);
+        assert!((fixed_y as f32 / one - y).abs() <= 0.5 / one);
+
+        let identity = QuadTransformer::new(Some(DEFAULT_DST_QUAD), None, None);
+        assert!(identity.transform_fixed(&(40000., 0.)).is_err());
+    }
+
+    #[test]
+    fn test_centered_destination() {
+        let src_quad: RectCorners = [(0., 0.), (640., 0.), (640., 480.), (0., 480.)];
+        let transformer = QuadTransformer::builder()
+            .with_src_quad(src_quad)
+            .with_centered_destination(true)
+            .with_ignore_outside_margin(0.)
+            .build()
+            .unwrap();
+
+        let (x, y) = transformer.transformed_center().unwrap();
+        assert!(x.abs() < 1e-5 && y.abs() < 1e-5);
+        let (x, y) = transformer.transform(&(0., 0.)).unwrap();
+        assert!((x + 0.5).abs() < 1e-5 && (y + 0.5).abs() < 1e-5);
+
+        assert_eq!(
+            transformer
+                .filter_points_inside(&[(-0.4, -0.4), (0.7, 0.2)])
+                .unwrap(),
+            vec![(-0.4, -0.4)]
+        );
+    }
+
+    #[test]
+    fn test_filter_points_inside_with_callback() {
+        let transformer = QuadTransformer::new(None, None, Some(0.1));
+        let points = [(0.5, 0.5), (1.5, 0.5), (1.05, 0.2), (0.3, -0.5)];
+
+        let mut rejected = Vec::new();
+        let inside = transformer.filter_points_inside_with_callback(&points, |point, distance| {
+            rejected.push((point, distance));
+        });
+
+        assert_eq!(inside, transformer.filter_points_inside(&points).unwrap());
+        let rejected_points: Vec<Point2D> = rejected.iter().map(|(p, _)| *p).collect();
+        let difference: Vec<Point2D> = points
+            .iter()
+            .filter(|p| !inside.contains(p))
+            .copied()
+            .collect();
+        assert_eq!(rejected_points, difference);
+        assert!((rejected[0].1 - 0.5).abs() < 1e-5);
+        assert!((rejected[1].1 - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_transform_array() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let points: [Point2D; 4] = [(250., 120.), (300., 300.), (480., 80.), (160., 400.)];
+
+        let transformed = transformer.transform_array(&points).unwrap();
+
+        assert_eq!(
+            transformed.to_vec(),
+            transformer.transform_many(&points).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hysteresis_filter() {
+        let transformer = QuadTransformer::new(Some(DEFAULT_DST_QUAD), None, None);
+        let mut filter = HysteresisFilter::new(transformer, 0.05, 0.05);
+
+        let steps = [
+            (0.5, true),
+            (1.02, true),
+            (0.98, true),
+            (1.04, true),
+            (1.1, false),
+            (0.98, false),
+            (1.02, false),
+            (0.97, false),
+            (0.9, true),
+        ];
+        for (x, expected) in steps {
+            assert_eq!(
+                filter.update(&[(x, 0.5)]).unwrap(),
+                vec![expected],
+                "x = {x}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dst_corners_in_source() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let corners = transformer.dst_corners_in_source().unwrap();
+
+        for (corner, expected) in corners.iter().zip(transformer.src_quad.unwrap().iter()) {
+            assert!((corner.0 - expected.0).abs() < 0.05);
+            assert!((corner.1 - expected.1).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_chain_from() {
+        let final_quad: RectCorners = [(0., 0.), (640., 0.), (640., 480.), (0., 480.)];
+        let upstream = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let downstream = QuadTransformer::chain_from(&upstream, final_quad, None).unwrap();
+
+        assert_eq!(downstream.src_quad, Some(EXAMPLE_DST));
+        let direct = QuadTransformer::new(Some(EXAMPLE_SRC), Some(final_quad), None);
+        for point in [(250., 120.), (300., 300.), (480., 80.)] {
+            let chained = downstream
+                .transform(&upstream.transform(&point).unwrap())
+                .unwrap();
+            let expected = direct.transform(&point).unwrap();
+            assert!(distance(chained.0, chained.1, expected.0, expected.1) < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_quantize_matrix() {
+        let original = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+
+        let mut quantized = original.clone();
+        quantized.quantize_matrix(6);
+        let once = quantized.matrix_opencv().unwrap();
+        quantized.quantize_matrix(6);
+        assert_eq!(quantized.matrix_opencv().unwrap(), once);
+
+        let point = (250., 120.);
+        let (x, y) = quantized.transform(&point).unwrap();
+        let (expected_x, expected_y) = original.transform(&point).unwrap();
+        assert!((x - expected_x).abs() < 0.01 && (y - expected_y).abs() < 0.01);
+
+        // More decimals than an f32 holds leave the matrix unchanged
+        for decimals in [60, 400, u32::MAX] {
+            let mut precise = original.clone();
+            precise.quantize_matrix(decimals);
+            assert_eq!(precise.matrix_opencv(), original.matrix_opencv());
+        }
+    }
+
+    #[test]
+    fn test_accumulate_heatmap() {
+        let src_quad: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let transformer = QuadTransformer::new(Some(src_quad), None, None);
+
+        let points = [
+            (80., 30.),
+            (82., 31.),
+            (79., 28.),
+            (10., 90.),
+            (100., 100.),
+            (150., 50.),
+        ];
+        let heatmap = transformer.accumulate_heatmap(&points, (4, 2)).unwrap();
+
+        assert_eq!(heatmap, vec![0, 0, 0, 3, 1, 0, 0, 1]);
+        assert!(transformer.accumulate_heatmap(&points, (0, 2)).is_err());
+
+        let pass_through = QuadTransformer::builder()
+            .with_src_quad(src_quad)
+            .with_nan_policy(NanPolicy::PassThrough)
+            .build()
+            .unwrap();
+        let heatmap = pass_through
+            .accumulate_heatmap(&[(f32::NAN, f32::NAN), (10., 90.)], (2, 2))
+            .unwrap();
+        assert_eq!(heatmap, vec![0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_smoothed_quad_transformer() {
+        let start: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let mut moved = start;
+        moved[1] = (120., 10.);
+        let mut smoothed = SmoothedQuadTransformer::new(QuadTransformer::new(None, None, None));
+
+        smoothed.update_src_quad(start, 0.5).unwrap();
+        assert_eq!(smoothed.smoothed_src_quad(), Some(start));
+
+        let mut previous_gap = distance(100., 0., 120., 10.);
+        for _ in 0..8 {
+            smoothed.update_src_quad(moved, 0.5).unwrap();
+            let corner = smoothed.smoothed_src_quad().unwrap()[1];
+            let gap = distance(corner.0, corner.1, 120., 10.);
+            assert!(gap < previous_gap);
+            previous_gap = gap;
+        }
+        assert!(previous_gap < 0.1);
+        assert!(smoothed.transformer().is_ready());
+        assert!(smoothed.update_src_quad(moved, 1.5).is_err());
+    }
+
+    #[test]
+    fn test_differs_from() {
+        let src_quad: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let transformer = QuadTransformer::new(Some(src_quad), None, None);
+
+        let tiny = transformer.with_perturbed_corner(2, (1e-3, 0.)).unwrap();
+        assert!(!transformer.differs_from(&tiny, 1e-4));
+
+        let large = transformer.with_perturbed_corner(2, (20., 0.)).unwrap();
+        assert!(transformer.differs_from(&large, 1e-4));
+    }
+
+    #[test]
+    fn test_between() {
+        // Two regions measured in the same camera image
+
+        let transformer = QuadTransformer::between(EXAMPLE_SRC, EXAMPLE_DST, None).unwrap();
+
+        let (x, y) = transformer.transform(&(250., 120.)).unwrap();
+        assert!((x - 117.275).abs() < 0.01 && (y - 530.92).abs() < 0.01);
+        for (a, b) in EXAMPLE_SRC.iter().zip(EXAMPLE_DST.iter()) {
+            let (x, y) = transformer.transform(a).unwrap();
+            assert!((x - b.0).abs() < 0.01 && (y - b.1).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_perspective_magnitude() {
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let affine_dst: RectCorners = [(1., 2.), (1., 4.), (3., 4.), (3., 2.)];
+        let perspective_dst: RectCorners = [(0.4, 0.), (0.6, 0.), (1., 1.), (0., 1.)];
+
+        let affine = QuadTransformer::new(Some(src_quad), Some(affine_dst), None);
+        let perspective = QuadTransformer::new(Some(src_quad), Some(perspective_dst), None);
+
+        assert!(affine.perspective_magnitude().unwrap() < 1e-5);
+        assert!(perspective.perspective_magnitude().unwrap() > 0.5);
+        assert!(QuadTransformer::new(None, None, None)
+            .perspective_magnitude()
+            .is_none());
+    }
+
+    #[test]
+    fn test_to_affine_approx() {
+        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let affine_dst: RectCorners = [(1., 2.), (1., 4.), (3., 4.), (3., 2.)];
+        let affine = QuadTransformer::new(Some(src_quad), Some(affine_dst), None);
+
+        let approx = affine.to_affine_approx().unwrap();
+        let exact = affine.transform_matrix.unwrap();
+        assert!((approx - exact).iter().all(|d| d.abs() < 1e-4));
+
+        let perspective_dst: RectCorners = [(0.1, 0.), (0.9, 0.), (1., 1.), (0., 1.)];
+        let perspective = QuadTransformer::new(Some(src_quad), Some(perspective_dst), None);
+        let approx = perspective.to_affine_approx().unwrap();
+        for (corner, expected) in src_quad.iter().zip(perspective_dst.iter()) {
+            let p = approx.transform_point(&Point2::new(corner.0, corner.1));
+            assert!(distance(p.x, p.y, expected.0, expected.1) < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_sample_grid() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let (cols, rows) = (5, 4);
+        let grid = transformer.sample_grid(cols, rows).unwrap();
+        assert_eq!(grid.len(), cols * rows * 2);
+
+        let corners = transformer.transformed_corners().unwrap();
+        let sample = |col: usize, row: usize| {
+            let i = 2 * (row * cols + col);
+            (grid[i], grid[i + 1])
+        };
+        let grid_corners = [
+            sample(0, 0),
+            sample(cols - 1, 0),
+            sample(cols - 1, rows - 1),
+            sample(0, rows - 1),
+        ];
+        for (actual, expected) in grid_corners.iter().zip(corners.iter()) {
+            assert!(distance(actual.0, actual.1, expected.0, expected.1) < 1e-3);
+        }
+
+        assert!(transformer.sample_grid(1, 4).is_err());
+    }
+
+    #[test]
+    fn test_implied_quads() {
+        let built = QuadTransformer::new(Some(UNIT_SQUARE), Some(EXAMPLE_DST), None);
+        let transformer = QuadTransformer::from_matrix(built.transform_matrix.unwrap(), None);
+
+        let implied_dst = transformer.implied_dst_quad().unwrap();
+        for (actual, expected) in implied_dst.iter().zip(EXAMPLE_DST.iter()) {
+            assert!(distance(actual.0, actual.1, expected.0, expected.1) < 1e-3);
+        }
+
+        let implied_src = transformer.implied_src_quad().unwrap();
+        let round_trip = transformer.transform_many(&implied_src).unwrap();
+        for (actual, expected) in round_trip.iter().zip(UNIT_SQUARE.iter()) {
+            assert!(distance(actual.0, actual.1, expected.0, expected.1) < 1e-3);
+        }
+
+        assert!(QuadTransformer::new(None, None, None)
+            .implied_dst_quad()
+            .is_none());
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn test_spawn_transform_worker() {
+        use std::sync::mpsc::channel;
+
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let points = [(158., 64.), (300., 200.), (495., 404.)];
+        let expected = transformer.transform_many(&points).unwrap();
+
+        let (point_tx, point_rx) = channel();
+        let (result_tx, result_rx) = channel();
+        let worker = transformer.spawn_transform_worker(point_rx, result_tx);
+        for point in points {
+            point_tx.send(point).unwrap();
+        }
+        drop(point_tx);
+
+        let results: Vec<Point2D> = result_rx.iter().map(|r| r.unwrap()).collect();
+        worker.join().unwrap();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_transform_clamped_src() {
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (100., 0.), (100., 100.), (0., 100.)]),
+            Some(EXAMPLE_DST),
+            None,
+        );
+
+        let clamped = transformer.transform_clamped_src(&(150., 50.)).unwrap();
+        assert!(distance_to_quad_edges(&EXAMPLE_DST, &clamped) < 1e-3);
+        let on_edge = transformer.transform(&(100., 50.)).unwrap();
+        assert!(distance(clamped.0, clamped.1, on_edge.0, on_edge.1) < 1e-3);
+
+        let inside = (40., 60.);
+        assert_eq!(
+            transformer.transform_clamped_src(&inside).unwrap(),
+            transformer.transform(&inside).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_checked_new() {
+        let degenerate: RectCorners = [(10., 10.), (10., 10.), (10., 10.), (10., 10.)];
+        assert!(QuadTransformer::checked_new(Some(degenerate), None, None).is_err());
+        assert!(
+            std::panic::catch_unwind(|| QuadTransformer::new(Some(degenerate), None, None))
+                .is_err()
+        );
+
+        let checked = QuadTransformer::checked_new(Some(EXAMPLE_SRC), None, Some(0.1)).unwrap();
+        let unchecked = QuadTransformer::new(Some(EXAMPLE_SRC), None, Some(0.1));
+        assert!(checked.approx_eq(&unchecked, 1e-6));
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let center = (30., 20.);
+        let scale = 2.;
+        let src_quad: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let dst_quad = src_quad.map(|(x, y)| {
+            (
+                center.0 + scale * (x - center.0),
+                center.1 + scale * (y - center.1),
+            )
+        });
+        let transformer = QuadTransformer::new(Some(src_quad), Some(dst_quad), None);
+
+        let fixed = transformer.fixed_point().unwrap();
+        assert!(distance(fixed.0, fixed.1, center.0, center.1) < 1e-2);
+
+        assert!(QuadTransformer::new(None, None, None)
+            .fixed_point()
+            .is_none());
+
+        // Every point is fixed
+        let identity = QuadTransformer::from_matrix(Matrix3::identity(), None);
+        let fixed = identity.fixed_point().unwrap();
+        let mapped = identity.transform(&fixed).unwrap();
+        assert!(distance(fixed.0, fixed.1, mapped.0, mapped.1) < 1e-4);
+
+        // Stretching along x only fixes the line x = 0
+        let unit: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
+        let stretch = QuadTransformer::new(
+            Some(unit),
+            Some([(0., 0.), (2., 0.), (2., 1.), (0., 1.)]),
+            None,
+        );
+        let fixed = stretch.fixed_point().unwrap();
+        assert!(fixed.0.abs() < 1e-3);
+        let mapped = stretch.transform(&fixed).unwrap();
+        assert!(distance(fixed.0, fixed.1, mapped.0, mapped.1) < 1e-3);
+    }
+
+    #[test]
+    fn test_to_css_matrix3d() {
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (1., 0.), (1., 1.), (0., 1.)]),
+            Some([(0.4, 0.), (0.6, 0.), (1., 1.), (0., 1.)]),
+            None,
+        );
+        let css = transformer.to_css_matrix3d().unwrap();
+        assert!(css.starts_with("matrix3d(") && css.ends_with(')'));
+
+        let values: Vec<f32> = css["matrix3d(".len()..css.len() - 1]
+            .split(", ")
+            .map(|v| v.parse().unwrap())
+            .collect();
+        assert_eq!(values.len(), 16);
+
+        let m = transformer.transform_matrix.unwrap();
+        assert_eq!(values[3], m[(2, 0)]);
+        assert_eq!(values[7], m[(2, 1)]);
+        assert_eq!(values[12], m[(0, 2)]);
+        assert_eq!(values[13], m[(1, 2)]);
+        assert_eq!(values[15], m[(2, 2)]);
+        assert_eq!(values[10], 1.);
+        assert!(m[(2, 1)].abs() > 0.1);
+    }
+
+    #[test]
+    fn test_transform_partition() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), None, Some(0.05));
+        let points = [
+            (300., 200.),
+            (10., 10.),
+            (494., 69.),
+            (800., 300.),
+            (160., 400.),
+        ];
+
+        let (inside, outside) = transformer.transform_partition(&points).unwrap();
+
+        let transformed = transformer.transform_many(&points).unwrap();
+        let expected_inside = transformer.filter_points_inside(&transformed).unwrap();
+        let expected_outside: Vec<Point2D> = transformed
+            .iter()
+            .filter(|p| !expected_inside.contains(p))
+            .copied()
+            .collect();
+        assert_eq!(inside, expected_inside);
+        assert_eq!(outside, expected_outside);
+        assert_eq!(inside.len(), 3);
+    }
+
+    #[test]
+    fn test_nan_policy() {
+        let with_policy = |policy| {
+            QuadTransformer::builder()
+                .with_src_quad(EXAMPLE_SRC)
+                .with_nan_policy(policy)
+                .build()
+                .unwrap()
+        };
+        let points = [
+            (300., 200.),
+            (f32::NAN, 100.),
+            (400., f32::INFINITY),
+            (200., 300.),
+        ];
+        let finite = [points[0], points[3]];
+
+        let skip = with_policy(NanPolicy::Skip);
+        assert_eq!(
+            skip.transform_many(&points).unwrap(),
+            skip.transform_many(&finite).unwrap()
+        );
+        let default = QuadTransformer::new(Some(EXAMPLE_SRC), None, None);
+        assert_eq!(default.transform_many(&points).unwrap().len(), 2);
+
+        assert!(with_policy(NanPolicy::Fail)
+            .transform_many(&points)
+            .is_err());
+        assert!(with_policy(NanPolicy::Fail).transform_many(&finite).is_ok());
+
+        let passed = with_policy(NanPolicy::PassThrough)
+            .transform_many(&points)
+            .unwrap();
+        assert_eq!(passed.len(), 4);
+        assert!(passed[1].0.is_nan());
+        assert!(passed[0].0.is_finite() && passed[3].1.is_finite());
+
+        // Methods which can't skip points still honour Fail
+        let fail = with_policy(NanPolicy::Fail);
+        let mut in_place = points;
+        assert!(fail.transform_slice_in_place(&mut in_place).is_err());
+        assert_eq!(in_place[0], points[0]);
+        assert!(fail.transform_array(&points).is_err());
+        assert!(fail.transform_inside_indexed(&points).is_err());
+        assert!(fail.transform_in_source_region(&points, 0.).is_err());
+        assert_eq!(
+            fail.transform_in_source_region(&finite, 0.).unwrap().len(),
+            2
+        );
+        let region = skip.transform_in_source_region(&points, 0.).unwrap();
+        assert_eq!(
+            region.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![0, 3]
+        );
+        assert!(fail.transform_array(&finite).is_ok());
+
+        let indexed = skip.transform_inside_indexed(&points).unwrap();
+        assert!(!indexed.is_empty());
+        assert!(indexed.iter().all(|(i, _)| *i == 0 || *i == 3));
+    }
+
+    #[test]
+    fn test_max_corner_shift() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), None, None);
+        let nudged = |amount: f32| {
+            let mut quad = EXAMPLE_SRC;
+            quad[2].0 += amount;
+            QuadTransformer::new(Some(quad), None, None)
+        };
+
+        assert!(transformer.max_corner_shift(&transformer).unwrap() < 1e-5);
+        let small = transformer.max_corner_shift(&nudged(1.)).unwrap();
+        let large = transformer.max_corner_shift(&nudged(20.)).unwrap();
+        assert!(small > 0.);
+        assert!(large > 10. * small);
+
+        let not_ready = QuadTransformer::new(None, None, None);
+        assert!(not_ready.max_corner_shift(&transformer).is_err());
+    }
+
+    #[test]
+    fn test_transform_uv_wrapped() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let wrapped = transformer.transform_uv_wrapped((1.25, -0.5)).unwrap();
+        let expected = transformer.uv_to_dst((0.25, 0.5)).unwrap();
+        assert!(distance(wrapped.0, wrapped.1, expected.0, expected.1) < 1e-4);
+    }
+
+    #[test]
+    fn test_transform_masked() {
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (100., 0.), (100., 100.), (0., 100.)]),
+            None,
+            None,
+        );
+        let mask: RectCorners = [(0.1, 0.1), (0.9, 0.1), (0.9, 0.9), (0.1, 0.9)];
+
+        let center = transformer
+            .transform_masked(&(50., 50.), &mask, 0.)
+            .unwrap();
+        assert!(center.is_some());
+        // On the edge of the destination quad, but outside the inset mask
+        assert!(transformer
+            .transform_masked(&(0., 50.), &mask, 0.)
+            .unwrap()
+            .is_none());
+        assert!(transformer
+            .transform_masked(&(5., 50.), &mask, 0.)
+            .unwrap()
+            .is_none());
+        assert!(transformer
+            .transform_masked(&(5., 50.), &mask, 0.06)
+            .unwrap()
+            .is_some());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_build_transform_lsq_par() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let src: Vec<Point2D> = (0..2000)
+            .map(|i| (150. + (i % 50) as f32 * 7., 60. + (i / 50) as f32 * 8.5))
+            .collect();
+        // Some deterministic "noise", so the fit is not exact
+        let dst: Vec<Point2D> = transformer
+            .transform_many(&src)
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| {
+                (
+                    x + ((i * 7) % 5) as f32 * 0.01,
+                    y - ((i * 3) % 4) as f32 * 0.01,
+                )
+            })
+            .collect();
+
+        let serial = build_transform_lsq(&src, &dst).unwrap();
+        let parallel = build_transform_lsq_par(&src, &dst).unwrap();
+        for point in [(158., 64.), (300., 250.), (495., 404.)] {
+            let p = Point2::new(point.0, point.1);
+            let (a, b) = (serial.transform_point(&p), parallel.transform_point(&p));
+            assert!(distance(a.x, a.y, b.x, b.y) < 1e-2);
+        }
+    }
 
-    let dst_quad_elements = vec![
-        dst_quad[0].0,
-        dst_quad[0].1,
-        dst_quad[1].0,
-        dst_quad[1].1,
-        dst_quad[2].0,
-        dst_quad[2].1,
-        dst_quad[3].0,
-        dst_quad[3].1,
-    ]
-    .into_iter();
+    #[test]
+    fn test_transform_circle() {
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (100., 0.), (100., 100.), (0., 100.)]),
+            Some([(10., 20.), (210., 20.), (210., 220.), (10., 220.)]),
+            None,
+        );
+        let center = (40., 60.);
+        let circle = transformer.transform_circle(center, 10., 16).unwrap();
+        assert_eq!(circle.len(), 16);
 
-    // let matrix_b: na::SMatrix<f32, 1, 8> = na::SMatrix::from_iterator(dst_quad_elements);
-    let matrix_b: na::SMatrix<f32, 1, 8> = na::SMatrix::from_iterator(dst_quad_elements);
+        let n = circle.len() as f32;
+        let (cx, cy) = circle
+            .iter()
+            .fold((0., 0.), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+        let expected_center = transformer.transform(&center).unwrap();
+        assert!(distance(cx, cy, expected_center.0, expected_center.1) < 1e-3);
+        for (x, y) in &circle {
+            let r = distance(*x, *y, expected_center.0, expected_center.1);
+            assert!((r - 20.).abs() < 1e-3);
+        }
 
-    // Solve for Ah = B
-    let coefficients = matrix_b * matrix_a.try_inverse().unwrap();
-    //
-    // Create a new 3x3 transform matrix using the elements from above
-    Matrix3::new(
-        coefficients[0],
-        coefficients[1],
-        coefficients[2],
-        coefficients[3],
-        coefficients[4],
-        coefficients[5],
-        coefficients[6],
-        coefficients[7],
-        1.,
-    )
-}
+        assert!(transformer.transform_circle(center, 10., 2).is_err());
+    }
 
-#[cfg(test)]
-mod tests {
+    #[test]
+    fn test_build_affine_transform() {
+        let src = [(158., 64.), (494., 69.), (300., 404.)];
+        let dst = [(100., 500.), (152., 564.), (90., 604.)];
+        let transformer = QuadTransformer::from_affine_points(src, dst, None).unwrap();
+        for (s, d) in src.iter().zip(dst.iter()) {
+            let (x, y) = transformer.transform(s).unwrap();
+            assert!(distance(x, y, d.0, d.1) < 1e-3);
+        }
+        assert_eq!(transformer.perspective_magnitude(), Some(0.));
 
-    use crate::*;
+        let collinear = [(0., 0.), (10., 10.), (20., 20.)];
+        assert!(build_affine_transform(collinear, dst).is_err());
+    }
 
-    use super::RectCorners;
+    #[test]
+    fn test_matrices_both() {
+        let mut transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let check_identity = |transformer: &QuadTransformer| {
+            let (forward, inverse) = transformer.matrices_both().unwrap();
+            let product = Matrix3::from_row_slice(&forward) * Matrix3::from_row_slice(&inverse);
+            assert!((product - Matrix3::identity())
+                .iter()
+                .all(|d| d.abs() < 1e-3));
+        };
+        check_identity(&transformer);
+
+        // The cached inverse follows changes to the matrix
+        transformer.with_output_scale(2., (0., 0.));
+        check_identity(&transformer);
+
+        assert!(QuadTransformer::new(None, None, None)
+            .matrices_both()
+            .is_none());
+    }
 
     #[test]
-    fn test_get_transform_matrix() {
-        // numbers as per https://github.com/jlouthan/perspective-transform#basic-usage
+    fn test_mul_composes_transformers() {
+        let a = QuadTransformer::new(
+            Some([(0., 0.), (1., 0.), (1., 1.), (0., 1.)]),
+            Some(EXAMPLE_DST),
+            None,
+        );
+        let b = QuadTransformer::new(Some(EXAMPLE_SRC), None, None);
+        let composed = &a * &b;
+        for point in [(158., 64.), (300., 200.), (480., 390.)] {
+            let (x, y) = composed.transform(&point).unwrap();
+            let expected = a.transform(&b.transform(&point).unwrap()).unwrap();
+            assert!(distance(x, y, expected.0, expected.1) < 1e-3);
+        }
+        assert!(!(&a * &QuadTransformer::new(None, None, None)).is_ready());
+    }
 
-        let src_quad = [158., 64., 494., 69., 495., 404., 158., 404.];
-        let src_quad: RectCorners = [
-            (src_quad[0], src_quad[1]),
-            (src_quad[2], src_quad[3]),
-            (src_quad[4], src_quad[5]),
-            (src_quad[6], src_quad[7]),
+    #[test]
+    fn test_inscribed_rect() {
+        let dst_quad: RectCorners = [(0.4, 0.), (0.6, 0.), (1., 1.), (0., 1.)];
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (1., 0.), (1., 1.), (0., 1.)]),
+            Some(dst_quad),
+            None,
+        );
+        let (min, max) = transformer.inscribed_rect().unwrap();
+        assert!(max.0 > min.0 && max.1 > min.1);
+        for corner in [min, (max.0, min.1), max, (min.0, max.1)] {
+            assert!(point_in_quad(&dst_quad, &corner, 1e-5));
+        }
+
+        // A rectangle is its own inscribed rectangle
+        let square = QuadTransformer::new(None, None, None);
+        let (min, max) = square.inscribed_rect().unwrap();
+        assert!(distance(min.0, min.1, 0., 0.) < 1e-5);
+        assert!(distance(max.0, max.1, 1., 1.) < 1e-5);
+    }
+
+    #[test]
+    fn test_is_self_intersecting() {
+        let proper: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        assert!(!is_self_intersecting(&proper));
+
+        let concave: RectCorners = [(0., 0.), (10., 0.), (3., 3.), (0., 10.)];
+        assert!(!is_self_intersecting(&concave));
+
+        let bowtie: RectCorners = [(0., 0.), (10., 0.), (0., 10.), (10., 10.)];
+        assert!(is_self_intersecting(&bowtie));
+        assert_eq!(
+            QuadTransformer::builder().with_src_quad(bowtie).validate()[0],
+            ValidationIssue::SelfIntersecting {
+                role: QuadRole::Source
+            }
+        );
+    }
+
+    #[test]
+    fn test_quad_transform_cache() {
+        let mut cache = QuadTransformCache::new(2, 0.01).unwrap();
+
+        cache.get_or_build(EXAMPLE_SRC, None, None).unwrap();
+        let mut jittered = EXAMPLE_SRC;
+        jittered[1].0 += 0.001;
+        let cached = cache.get_or_build(jittered, None, None).unwrap();
+        // Reused the first transformer, rather than building from the jittered quad
+        assert_eq!(cached.src_quad, Some(EXAMPLE_SRC));
+        assert_eq!(cache.len(), 1);
+
+        let other: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        let third: RectCorners = [(0., 0.), (20., 0.), (20., 20.), (0., 20.)];
+        cache.get_or_build(other, None, None).unwrap();
+        cache.get_or_build(third, None, None).unwrap();
+        assert_eq!(cache.len(), 2);
+        // The least recently used (the first quad) was evicted
+        assert!(cache
+            .entries
+            .iter()
+            .all(|(_, t)| t.src_quad != Some(EXAMPLE_SRC)));
+
+        for quantum in [0., -0.01, f32::NAN, f32::INFINITY] {
+            assert!(QuadTransformCache::new(2, quantum).is_err());
+        }
+    }
+
+    #[test]
+    fn test_scaled_about_center() {
+        let transformer = QuadTransformer::scaled_about_center(EXAMPLE_SRC, 2., None).unwrap();
+        let (cx, cy) = quad_centroid(&EXAMPLE_SRC);
+        for corner in EXAMPLE_SRC {
+            let (x, y) = transformer.transform(&corner).unwrap();
+            let expected = 2. * distance(corner.0, corner.1, cx, cy);
+            assert!((distance(x, y, cx, cy) - expected).abs() < 1e-2);
+        }
+        assert!(QuadTransformer::scaled_about_center(EXAMPLE_SRC, 0., None).is_err());
+    }
+
+    #[test]
+    fn test_transform_many_with_w() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let points = [(158., 64.), (300., 200.), (495., 404.)];
+        let results = transformer.transform_many_with_w(&points).unwrap();
+        assert_eq!(results.len(), points.len());
+        for (point, (transformed, w)) in points.iter().zip(results) {
+            assert_eq!(transformed, transformer.transform(point).unwrap());
+            assert_eq!(w, transformer.transform_homogeneous(point).unwrap().2);
+        }
+    }
+
+    #[test]
+    fn test_aspect_fill() {
+        let transformer = QuadTransformer::builder()
+            .with_src_quad([(0., 0.), (200., 0.), (200., 100.), (0., 100.)])
+            .with_aspect_mode(AspectMode::Fill)
+            .build()
+            .unwrap();
+
+        // The middle 100x100 of the source fills the square destination
+        let expected = [
+            ((50., 0.), (0., 0.)),
+            ((150., 0.), (1., 0.)),
+            ((150., 100.), (1., 1.)),
+            ((50., 100.), (0., 1.)),
+            ((100., 50.), (0.5, 0.5)),
         ];
+        for (src, dst) in expected {
+            let (x, y) = transformer.transform(&src).unwrap();
+            assert!(distance(x, y, dst.0, dst.1) < 1e-4);
+        }
+        // ...and the sides are cropped
+        let (x, _) = transformer.transform(&(0., 50.)).unwrap();
+        assert!((x + 0.5).abs() < 1e-4);
+    }
 
-        let dst_quad = [100., 500., 152., 564., 148., 604., 100., 560.];
-        let dst_quad: RectCorners = [
-            (dst_quad[0], dst_quad[1]),
-            (dst_quad[2], dst_quad[3]),
-            (dst_quad[4], dst_quad[5]),
-            (dst_quad[6], dst_quad[7]),
+    #[test]
+    fn test_edge_midpoints() {
+        let square: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        assert_eq!(
+            quad_edge_midpoints(&square),
+            [(5., 0.), (10., 5.), (5., 10.), (0., 5.)]
+        );
+
+        let transformer = QuadTransformer::new(Some(square), None, None);
+        assert_eq!(
+            transformer.dst_edge_midpoints(),
+            Some([(0.5, 0.), (1., 0.5), (0.5, 1.), (0., 0.5)])
+        );
+        assert!(QuadTransformer::new(None, None, None)
+            .dst_edge_midpoints()
+            .is_none());
+    }
+
+    #[test]
+    fn test_not_ready_policy() {
+        let point = (12., 34.);
+
+        let strict = QuadTransformer::builder().build().unwrap();
+        assert!(strict.transform(&point).is_err());
+        assert!(strict.transform_many(&[point]).is_err());
+
+        let passthrough = QuadTransformer::builder()
+            .with_not_ready_policy(NotReadyPolicy::Passthrough)
+            .build()
+            .unwrap();
+        assert_eq!(passthrough.transform(&point).unwrap(), point);
+        assert_eq!(passthrough.transform_many(&[point]).unwrap(), vec![point]);
+
+        // The other batch methods follow the same policy
+        let mut in_place = [point];
+        assert!(strict.transform_slice_in_place(&mut in_place).is_err());
+        assert!(strict.transform_array(&[point]).is_err());
+        assert!(strict.transform_many_with_w(&[point]).is_err());
+        assert!(strict.transform_in_source_region(&[point], 0.).is_err());
+        assert!(strict.transform_inside_indexed(&[point]).is_err());
+
+        passthrough.transform_slice_in_place(&mut in_place).unwrap();
+        assert_eq!(in_place, [point]);
+        assert_eq!(passthrough.transform_array(&[point]).unwrap(), [point]);
+        assert_eq!(
+            passthrough.transform_many_with_w(&[point]).unwrap(),
+            vec![(point, 1.)]
+        );
+        assert!(passthrough
+            .transform_in_source_region(&[point], 0.)
+            .unwrap()
+            .is_empty());
+        #[cfg(feature = "simd")]
+        {
+            assert!(strict.transform_many_simd(&[point]).is_err());
+            assert_eq!(
+                passthrough.transform_many_simd(&[point]).unwrap(),
+                vec![point]
+            );
+        }
+    }
+
+    #[test]
+    fn test_quad_overlap_area() {
+        let a: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        let b: RectCorners = [(6., 4.), (16., 4.), (16., 14.), (6., 14.)];
+        assert!((quad_overlap_area(&a, &b) - 24.).abs() < 1e-4);
+        assert!((quad_overlap_area(&a, &a) - 100.).abs() < 1e-4);
+
+        let disjoint: RectCorners = [(20., 0.), (30., 0.), (30., 10.), (20., 10.)];
+        assert_eq!(quad_overlap_area(&a, &disjoint), 0.);
+    }
+
+    #[test]
+    fn test_to_local_cell() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        // The centre of cell (2, 1) of a 4x4 grid across the skewed quad
+        let dst_point = bilinear_point(&EXAMPLE_DST, (0.625, 0.375));
+        let src_point = transformer.inverse_transform(&dst_point).unwrap();
+        assert_eq!(
+            transformer.to_local_cell(&src_point, (4, 4)).unwrap(),
+            Some((2, 1))
+        );
+
+        assert_eq!(transformer.to_local_cell(&(0., 0.), (4, 4)).unwrap(), None);
+        assert!(transformer.to_local_cell(&src_point, (0, 4)).is_err());
+    }
+
+    #[test]
+    fn test_all_inside_any_inside() {
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (100., 0.), (100., 100.), (0., 100.)]),
+            None,
+            Some(0.),
+        );
+        let inside = [(10., 10.), (50., 50.), (90., 20.)];
+        let partly = [(10., 10.), (150., 50.)];
+        let outside = [(-10., 10.), (150., 50.), (50., 200.)];
+
+        assert!(transformer.all_inside(&inside));
+        assert!(transformer.any_inside(&inside));
+        assert!(!transformer.all_inside(&partly));
+        assert!(transformer.any_inside(&partly));
+        assert!(!transformer.all_inside(&outside));
+        assert!(!transformer.any_inside(&outside));
+    }
+
+    #[test]
+    fn test_smoothed_output() {
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (100., 0.), (100., 100.), (0., 100.)]),
+            None,
+            None,
+        );
+        let mut smoothed = SmoothedOutput::new(transformer, 0.5).unwrap();
+
+        assert_eq!(smoothed.transform_smoothed(1, (0., 0.)).unwrap(), (0., 0.));
+        // Step to (100, 100), i.e. (1, 1) in destination space
+        let mut previous = 0.;
+        for _ in 0..5 {
+            let (x, y) = smoothed.transform_smoothed(1, (100., 100.)).unwrap();
+            assert!(x > previous && x < 1.);
+            assert!((x - y).abs() < 1e-6);
+            previous = x;
+        }
+        assert!((1. - previous) < 0.05);
+
+        // Other IDs are independent
+        assert_eq!(
+            smoothed.transform_smoothed(2, (50., 0.)).unwrap(),
+            (0.5, 0.)
+        );
+        assert!(SmoothedOutput::new(QuadTransformer::new(None, None, None), 1.5).is_err());
+    }
+
+    #[test]
+    fn test_matrix_row_and_column_major() {
+        let matrix = Matrix3::new(1., 2., 3., 4., 5., 6., 7., 8., 9.);
+        let transformer = QuadTransformer::from_matrix(matrix, None);
+
+        let row_major = transformer.matrix_row_major().unwrap();
+        let column_major = transformer.matrix_column_major().unwrap();
+        assert_eq!(row_major, [1., 2., 3., 4., 5., 6., 7., 8., 9.]);
+        assert_eq!(column_major, [1., 4., 7., 2., 5., 8., 3., 6., 9.]);
+        for row in 0..3 {
+            for col in 0..3 {
+                assert_eq!(row_major[row * 3 + col], column_major[col * 3 + row]);
+            }
+        }
+        assert!(QuadTransformer::new(None, None, None)
+            .matrix_row_major()
+            .is_none());
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_transform_f16() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        for point in [(158., 64.), (300., 200.), (495., 404.)] {
+            let (x, y) = transformer.transform(&point).unwrap();
+            let (hx, hy) = transformer.transform_f16(&point).unwrap();
+            assert!((hx.to_f32() - x).abs() <= x.abs() * 1e-3);
+            assert!((hy.to_f32() - y).abs() <= y.abs() * 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_error_stats() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let src = [
+            (158., 64.),
+            (300., 200.),
+            (495., 404.),
+            (200., 300.),
+            (400., 100.),
         ];
+        let mut expected = transformer.transform_many(&src).unwrap();
+        expected[0].0 += 0.1;
+        expected[1].1 -= 0.2;
+        expected[3].0 += 50.;
 
-        let transform_matrix = build_transform(&src_quad, &dst_quad);
+        let stats = transformer.error_stats(&src, &expected).unwrap();
+        assert!((stats.max - 50.).abs() < 1e-2);
+        assert!(stats.min < 1e-3);
+        assert!((stats.median - 0.1).abs() < 1e-2);
+        assert!(stats.mean > 10.);
 
-        let src_point = (250., 120.);
+        assert!(transformer.error_stats(&src, &expected[..2]).is_err());
+    }
 
-        let result = {
-            let (x, y) = (src_point.0, src_point.1);
-            let nalgebra_point = nalgebra::Point2::new(x, y);
+    #[test]
+    fn test_contains_normalized() {
+        let unit = QuadTransformer::new(Some(EXAMPLE_SRC), None, None);
+        let pixels = QuadTransformer::new(
+            Some(EXAMPLE_SRC),
+            Some([(0., 0.), (1920., 0.), (1920., 1080.), (0., 1080.)]),
+            None,
+        );
+        for (transformer, (width, height)) in [(unit, (1., 1.)), (pixels, (1920., 1080.))] {
+            let at = |u: f32, v: f32| (u * width, v * height);
+            assert!(transformer.contains_normalized(&at(0.5, 0.5), 0.));
+            assert!(!transformer.contains_normalized(&at(1.05, 0.5), 0.));
+            assert!(transformer.contains_normalized(&at(1.05, 0.5), 0.1));
+            assert!(!transformer.contains_normalized(&at(0.5, -0.2), 0.1));
+        }
+    }
 
-            let transformed = transform_matrix.transform_point(&nalgebra_point);
-            (transformed.x, transformed.y)
-        };
+    #[test]
+    fn test_transform_inside_indexed() {
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (100., 0.), (100., 100.), (0., 100.)]),
+            None,
+            Some(0.),
+        );
+        let points = [(-10., 10.), (50., 50.), (150., 50.), (20., 80.)];
+        let inside = transformer.transform_inside_indexed(&points).unwrap();
+        let indices: Vec<usize> = inside.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, vec![1, 3]);
+        for (i, point) in inside {
+            assert_eq!(point, transformer.transform(&points[i]).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_to_unit_square_from() {
+        for origin in 0..4 {
+            let transformer =
+                QuadTransformer::to_unit_square_from(EXAMPLE_SRC, origin, None).unwrap();
+            let at_origin = transformer.transform(&EXAMPLE_SRC[origin]).unwrap();
+            assert!(distance(at_origin.0, at_origin.1, 0., 0.) < 1e-4);
+            let next = transformer
+                .transform(&EXAMPLE_SRC[(origin + 1) % 4])
+                .unwrap();
+            assert!(distance(next.0, next.1, 1., 0.) < 1e-4);
+            let previous = transformer
+                .transform(&EXAMPLE_SRC[(origin + 3) % 4])
+                .unwrap();
+            assert!(distance(previous.0, previous.1, 0., 1.) < 1e-4);
+        }
+        assert!(QuadTransformer::to_unit_square_from(EXAMPLE_SRC, 4, None).is_err());
+    }
+
+    #[test]
+    fn test_blend() {
+        let a = QuadTransformer::new(
+            Some([(0., 0.), (100., 0.), (100., 100.), (0., 100.)]),
+            None,
+            None,
+        );
+        let b = QuadTransformer::new(
+            Some([(20., 10.), (140., 0.), (120., 120.), (0., 110.)]),
+            None,
+            None,
+        );
+        let blended = QuadTransformer::blend(&[&a, &b], &[1., 1.]).unwrap();
+        let (src_a, src_b) = (a.src_quad.unwrap(), b.src_quad.unwrap());
+        for i in 0..4 {
+            let midpoint = (
+                (src_a[i].0 + src_b[i].0) / 2.,
+                (src_a[i].1 + src_b[i].1) / 2.,
+            );
+            let (x, y) = blended.transform(&midpoint).unwrap();
+            assert!(distance(x, y, UNIT_SQUARE[i].0, UNIT_SQUARE[i].1) < 1e-4);
+        }
+
+        assert!(QuadTransformer::blend(&[&a, &b], &[1.]).is_err());
+        assert!(QuadTransformer::blend(&[&a, &b], &[0., 0.]).is_err());
+    }
+
+    #[test]
+    fn test_warp_field() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let field = transformer.warp_field(4, 3).unwrap();
+        assert_eq!(field.len(), 12);
+        for (src, dst, displacement) in &field {
+            assert_eq!(*dst, transformer.transform(src).unwrap());
+            assert_eq!(*displacement, (dst.0 - src.0, dst.1 - src.1));
+        }
+        assert_eq!(field[0].0, (158., 64.));
+        assert!(QuadTransformer::new(None, None, None)
+            .warp_field(4, 3)
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_base_with_deltas() {
+        let unmoved = QuadTransformer::from_base_with_deltas(
+            EXAMPLE_SRC,
+            [(0., 0.); 4],
+            Some(EXAMPLE_DST),
+            None,
+        )
+        .unwrap();
+        let expected = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        assert!(unmoved.approx_eq(&expected, 1e-6));
+
+        let deltas = [(5., -3.), (0., 0.), (-10., 2.), (0., 0.)];
+        let moved =
+            QuadTransformer::from_base_with_deltas(EXAMPLE_SRC, deltas, Some(EXAMPLE_DST), None)
+                .unwrap();
+        let src_quad = moved.src_quad.unwrap();
+        assert_eq!(src_quad[0], (163., 61.));
+        assert_eq!(src_quad[1], EXAMPLE_SRC[1]);
+        assert_eq!(src_quad[2], (485., 406.));
+        let (x, y) = moved.transform(&(163., 61.)).unwrap();
+        assert!(distance(x, y, EXAMPLE_DST[0].0, EXAMPLE_DST[0].1) < 1e-2);
+    }
+
+    #[test]
+    fn test_quad_calibrator_expand_to_include() {
+        let src_quad: RectCorners = [(0., 0.), (100., 0.), (100., 100.), (0., 100.)];
+        let mut calibrator = QuadCalibrator::new(QuadTransformer::new(Some(src_quad), None, None));
+
+        // Nearest to the right edge, which moves out to meet it
+        calibrator.expand_to_include((150., 50.)).unwrap();
+        let expanded = calibrator.transformer().src_quad.unwrap();
+        assert_eq!(expanded, [(0., 0.), (150., 0.), (150., 100.), (0., 100.)]);
+        assert!(point_in_quad(&expanded, &(150., 50.), 1e-4));
+        let (x, _) = calibrator.transformer().transform(&(150., 50.)).unwrap();
+        assert!((x - 1.).abs() < 1e-4);
+
+        // Nearest to a corner, which moves onto it
+        calibrator.expand_to_include((-20., -10.)).unwrap();
+        let expanded = calibrator.transformer().src_quad.unwrap();
+        assert_eq!(expanded[0], (-20., -10.));
+        assert!(point_in_quad(&expanded, &(-20., -10.), 1e-4));
+
+        // Points inside change nothing
+        calibrator.expand_to_include((50., 50.)).unwrap();
+        assert_eq!(calibrator.transformer().src_quad.unwrap(), expanded);
+    }
+
+    #[test]
+    fn test_edge_scales() {
+        // Stretched 3x horizontally and 2x vertically
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (10., 0.), (10., 10.), (0., 10.)]),
+            Some([(0., 0.), (30., 0.), (30., 20.), (0., 20.)]),
+            None,
+        );
+        let scales = transformer.edge_scales().unwrap();
+        for (scale, expected) in scales.iter().zip([3., 2., 3., 2.]) {
+            assert!((scale - expected).abs() < 1e-4);
+        }
+
+        let perspective = QuadTransformer::new(
+            Some([(0., 0.), (1., 0.), (1., 1.), (0., 1.)]),
+            Some([(0.4, 0.), (0.6, 0.), (1., 1.), (0., 1.)]),
+            None,
+        );
+        let scales = perspective.edge_scales().unwrap();
+        assert!(scales[0] < scales[2]);
+    }
 
+    #[test]
+    fn test_transform_clamped_dst() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let outside = (1000., -500.);
+        let (x, y) = transformer.transform(&outside).unwrap();
+        assert!(!(100. ..=152.).contains(&x) || !(500. ..=604.).contains(&y));
+
+        let (cx, cy) = transformer.transform_clamped_dst(&outside).unwrap();
+        assert_eq!(cx, x.clamp(100., 152.));
+        assert_eq!(cy, y.clamp(500., 604.));
+
+        let inside = (300., 200.);
         assert_eq!(
-            (result.0.round(), result.1.round()),
-            (
-                117.27521125839255_f32.round(),
-                530.9202410878403_f32.round(),
-            ),
+            transformer.transform_clamped_dst(&inside).unwrap(),
+            transformer.transform(&inside).unwrap()
         );
     }
 
     #[test]
-    fn test_get_transform_matrix_simple() {
-        // numbers as per https://github.com/jlouthan/perspective-transform#basic-usage
+    fn test_ffi_round_trip() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let ffi = transformer.to_ffi();
+        assert!(ffi.ready);
+        let restored = QuadTransformer::from_ffi(&ffi);
+        for point in [(158., 64.), (300., 200.), (495., 404.)] {
+            assert_eq!(
+                restored.transform(&point).unwrap(),
+                transformer.transform(&point).unwrap()
+            );
+        }
 
-        let src_quad: RectCorners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)];
-        let dst_quad: RectCorners = [(1., 2.), (1., 4.), (3., 4.), (3., 2.)];
+        let not_ready = QuadTransformer::new(None, None, None).to_ffi();
+        assert!(!not_ready.ready);
+        assert!(!QuadTransformer::from_ffi(&not_ready).is_ready());
+    }
 
-        let transform_matrix = build_transform(&src_quad, &dst_quad);
+    #[test]
+    fn test_with_dynamic_offset() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let offset = (12.5, -30.);
+        let shifted = transformer.with_dynamic_offset(offset);
+        for point in [(158., 64.), (300., 200.), (495., 404.)] {
+            let (x, y) = transformer.transform(&point).unwrap();
+            let (sx, sy) = shifted.transform(&point).unwrap();
+            assert!(distance(sx, sy, x + offset.0, y + offset.1) < 1e-3);
+        }
+    }
 
-        // The transform matrix is a little different to the example in https://blog.mbedded.ninja/mathematics/geometry/projective-transformations/
-        // because their point order is somehow different. The result is the same.
-        // assert_eq!(
-        //     transform_matrix,
-        //     Matrix3::new(2., 0., 1., 0., 2., 2., 0., 0., 1.)
-        // );
+    #[test]
+    fn test_dst_edges_in_source() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let edges = transformer.dst_edges_in_source(5).unwrap();
+        let corners = transformer.dst_corners_in_source().unwrap();
+        assert_eq!(edges.len(), 4);
+        for (i, edge) in edges.iter().enumerate() {
+            assert_eq!(edge.len(), 5);
+            let (start, end) = (corners[i], corners[(i + 1) % 4]);
+            assert!(distance(edge[0].0, edge[0].1, start.0, start.1) < 1e-3);
+            assert!(distance(edge[4].0, edge[4].1, end.0, end.1) < 1e-3);
+        }
+        assert!(transformer.dst_edges_in_source(1).is_err());
+    }
 
-        let src_point = (0.5, 0.5);
+    #[test]
+    fn test_from_auto_rotated_src() {
+        let expected = QuadTransformer::new(Some(EXAMPLE_SRC), None, None);
 
-        let result = {
-            let (x, y) = (src_point.0, src_point.1);
-            let nalgebra_point = nalgebra::Point2::new(x, y);
+        let rotated = [
+            EXAMPLE_SRC[2],
+            EXAMPLE_SRC[3],
+            EXAMPLE_SRC[0],
+            EXAMPLE_SRC[1],
+        ];
+        let reversed = [
+            EXAMPLE_SRC[1],
+            EXAMPLE_SRC[0],
+            EXAMPLE_SRC[3],
+            EXAMPLE_SRC[2],
+        ];
+        for points in [rotated, reversed] {
+            let transformer = QuadTransformer::from_auto_rotated_src(points, None, None).unwrap();
+            assert_eq!(transformer.src_quad, Some(EXAMPLE_SRC));
+            assert!(transformer.approx_eq(&expected, 1e-6));
+            let (x, y) = transformer.transform(&EXAMPLE_SRC[0]).unwrap();
+            assert!(distance(x, y, 0., 0.) < 1e-4);
+        }
+    }
 
-            let transformed = transform_matrix.transform_point(&nalgebra_point);
-            (transformed.x, transformed.y)
-        };
+    #[test]
+    fn test_affine_condition_number() {
+        let src_quad: RectCorners = [(0., 0.), (10., 0.), (10., 10.), (0., 10.)];
+        let uniform = QuadTransformer::new(
+            Some(src_quad),
+            Some([(5., 5.), (35., 5.), (35., 35.), (5., 35.)]),
+            None,
+        );
+        assert!((uniform.affine_condition_number().unwrap() - 1.).abs() < 1e-4);
 
-        assert_eq!(result, (2., 3.));
+        let anisotropic = QuadTransformer::new(
+            Some(src_quad),
+            Some([(0., 0.), (100., 0.), (100., 0.1), (0., 0.1)]),
+            None,
+        );
+        assert!(anisotropic.affine_condition_number().unwrap() > 100.);
+
+        assert!(QuadTransformer::new(None, None, None)
+            .affine_condition_number()
+            .is_none());
+    }
+
+    #[test]
+    fn test_inverse_transform_into() {
+        let transformer = QuadTransformer::new(Some(EXAMPLE_SRC), Some(EXAMPLE_DST), None);
+        let frame_a = [(100., 500.), (120., 550.), (148., 604.)];
+        let frame_b = [(110., 540.)];
+
+        let mut out = Vec::new();
+        transformer
+            .inverse_transform_into(&frame_a, &mut out)
+            .unwrap();
+        assert_eq!(out, transformer.inverse_transform_many(&frame_a).unwrap());
+        let capacity = out.capacity();
+
+        transformer
+            .inverse_transform_into(&frame_b, &mut out)
+            .unwrap();
+        assert_eq!(out, transformer.inverse_transform_many(&frame_b).unwrap());
+        assert_eq!(out.len(), 1);
+        assert_eq!(out.capacity(), capacity);
+        for (point, expected) in out.iter().zip(frame_b.iter()) {
+            assert_eq!(*point, transformer.inverse_transform(expected).unwrap());
+        }
+
+        let not_ready = QuadTransformer::new(None, None, None);
+        assert!(not_ready
+            .inverse_transform_into(&frame_a, &mut out)
+            .is_err());
+        assert_eq!(out.len(), 1);
+    }
+
+    #[test]
+    fn test_dst_quad_from_aspect() {
+        let dst_quad = dst_quad_from_aspect(16. / 9., 1080.).unwrap();
+        let (min, max) = bounds(&dst_quad);
+        assert_eq!(min, (0., 0.));
+        assert!((max.0 - 1920.).abs() < 1e-3);
+        assert_eq!(max.1, 1080.);
+        assert!(((max.0 - min.0) / (max.1 - min.1) - 16. / 9.).abs() < 1e-6);
+
+        let transformer = QuadTransformer::new(
+            Some([(0., 0.), (1., 0.), (1., 1.), (0., 1.)]),
+            Some(dst_quad),
+            None,
+        );
+        let (x, y) = transformer.transform(&(1., 1.)).unwrap();
+        assert!(distance(x, y, max.0, max.1) < 1e-2);
+
+        for (aspect, height) in [
+            (-1., 100.),
+            (1.5, 0.),
+            (f32::NAN, 100.),
+            (1.5, f32::INFINITY),
+        ] {
+            assert!(dst_quad_from_aspect(aspect, height).is_err());
+        }
+        // Finite inputs whose product overflows
+        assert!(dst_quad_from_aspect(f32::MAX, 10.).is_err());
     }
 }